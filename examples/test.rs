@@ -1,9 +1,9 @@
-use rufi::MenuApp;
+use rufi::{Error, MenuApp};
 
 pub const WIN_W: u32 = 600;
 
-fn main() {
-    let app: MenuApp<String> = MenuApp::new(WIN_W, "Rufi test");
+fn main() -> Result<(), Error> {
+    let app: MenuApp<String> = MenuApp::new(WIN_W, "Rufi test")?;
 
     app.main_loop(|query| {
         let mut acc = vec![];
@@ -14,7 +14,8 @@ fn main() {
         }
 
         result
-    });
+    })?;
 
     std::thread::sleep(std::time::Duration::from_secs(1));
+    Ok(())
 }
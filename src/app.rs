@@ -1,141 +1,316 @@
 // use crate::renderer::Renderer;
 // use crate::support::convert_event;
-use conrod_core::position::{Place, Relative};
-use conrod_core::text::FontCollection;
-use conrod_core::{widget_ids, Borderable, Sizeable, Ui};
+use conrod_core::position::Relative;
+use conrod_core::text::font;
+use conrod_core::{widget_ids, Borderable, Color, FontSize, Scalar, Sizeable, Ui};
 use std::cmp::min;
-use std::fmt::Display;
 
-use crate::renderer::Renderer;
+use crate::builder::MenuAppBuilder;
+use crate::controller::{AppEvent, AppState, Input, MenuController, Outcome};
+use crate::cursor::cursor_position;
+use crate::error::Error;
+use crate::font::{FallbackFonts, FontFaces};
+use crate::icon::IconCache;
+use crate::item::MenuItem;
+use crate::keys::Keymap;
+#[cfg(all(feature = "layer-shell", unix, not(target_os = "macos")))]
+use crate::layer_shell::LayerShell;
+use crate::markup::parse_markup;
+use crate::monitor::{monitor_containing, MonitorSelection};
+use crate::placement::Placement;
+use crate::raster::Rasterizer;
+use crate::renderer::{request_adapter, WindowRenderer};
+use crate::row::ItemRow;
+use crate::search::{
+    search_immediate, without_positions, CancellationToken, ResultSink, SearchBackend, SearchMode,
+    SearchWorker,
+};
 use crate::window::convert_event;
-use std::sync::mpsc::channel;
-use std::time::Duration;
-use winit::dpi::{LogicalSize, PhysicalPosition};
-use winit::event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
+use futures::executor::block_on;
+use futures::{stream, Future, FutureExt, Stream, StreamExt};
+use image::RgbaImage;
+use winit::dpi::LogicalSize;
+use winit::event::{DeviceEvent, ElementState, Event, KeyboardInput, ModifiersState, WindowEvent};
 use winit::{
-    event_loop::{ControlFlow, EventLoop},
+    event_loop::{ControlFlow, EventLoop, EventLoopProxy},
     platform::desktop::EventLoopExtDesktop,
 };
 
-pub const INITIAL_HEIGHT: u32 = 26;
+pub const ICON_SIZE: u32 = 32;
+const SCROLLBAR_WIDTH: Scalar = 4.0;
 
-pub struct AppState<Item: Display> {
-    items: Vec<Item>,
-    selected: usize,
-    search: String,
+/// The fonts loaded into the ui
+#[derive(Debug, Clone)]
+pub struct Fonts {
+    pub regular: font::Id,
+    pub bold: font::Id,
+    pub italic: font::Id,
+    pub bold_italic: font::Id,
+    /// Fonts for the characters missing from the other fonts, in order
+    pub fallback: Vec<font::Id>,
 }
 
-impl<Item: Display> AppState<Item> {
-    pub fn set_search(&mut self, search: String) {
-        self.search = search
-    }
+/// Sizes used to lay out the menu
+#[derive(Debug, Clone, Copy)]
+pub struct Layout {
+    /// Font size for the search input and item titles
+    pub font_size: FontSize,
+    pub subtitle_font_size: FontSize,
+    pub max_rows: usize,
+    /// Keep the window at the height for `max_rows` rows, even with less results
+    pub fixed_height: bool,
+    /// Space between the edge of the window and the content, the border is drawn inside of it
+    pub margin: Scalar,
+    pub border_width: Scalar,
+    /// Color of the border, the text color when not set
+    pub border_color: Option<Color>,
+    /// Height of the result rows, instead of fitting them to the fonts
+    pub row_height: Option<u32>,
 }
 
-#[derive(Debug, Clone)]
-pub enum AppEvent {
-    Search(String),
-    Continue,
-    Exit,
+impl Layout {
+    /// Height of the search input
+    fn input_height(&self) -> u32 {
+        self.font_size + 2
+    }
+
+    /// Space taken by the border and margin on each side of the window
+    fn inset(&self) -> Scalar {
+        self.margin.max(self.border_width)
+    }
 }
 
 /// A demonstration of some application state we want to control with a conrod GUI.
-pub struct MenuApp<Item: Display + Send + 'static> {
-    state: AppState<Item>,
-    ids: Ids,
-    ui: Ui,
-    title: String,
+pub struct MenuApp<Item: MenuItem + Send + 'static> {
+    pub(crate) controller: MenuController<Item>,
+    pub(crate) ids: Ids,
+    pub(crate) ui: Ui,
+    pub(crate) fonts: Fonts,
+    pub(crate) fallback_fonts: FallbackFonts,
+    pub(crate) layout: Layout,
+    pub(crate) placement: Placement,
+    pub(crate) monitor_selection: MonitorSelection,
+    pub(crate) icon_theme: String,
+    pub(crate) search_mode: SearchMode,
+    pub(crate) title: String,
 }
 
-impl<Item: Display + Send + 'static> MenuApp<Item> {
+impl<Item: MenuItem + Send + 'static> MenuApp<Item> {
     /// Simple constructor for the `DemoApp`.
-    pub fn new(width: u32, title: &str) -> Self {
+    ///
+    /// Use `MenuAppBuilder` for more options.
+    pub fn new(width: u32, title: &str) -> Result<Self, Error> {
+        Ok(MenuAppBuilder::new().width(width).title(title).build()?)
+    }
+
+    pub(crate) fn from_builder(
+        builder: MenuAppBuilder,
+        faces: FontFaces,
+        fallback_fonts: FallbackFonts,
+        layout: Layout,
+    ) -> Self {
+        // the theme is moved into the ui below
+        let controller = builder.controller();
+
         // Create Ui and Ids of widgets to instantiate
-        let mut ui = conrod_core::UiBuilder::new([width as f64, INITIAL_HEIGHT as f64])
-            .theme(default_theme())
+        let initial_height = layout.input_height() as f64 + 2.0 * layout.inset();
+        let mut ui = conrod_core::UiBuilder::new([builder.width as f64, initial_height])
+            .theme(builder.theme)
             .build();
         let ids = Ids::new(ui.widget_id_generator());
 
-        let font_collection = FontCollection::from_bytes(
-            include_bytes!("../assets/fonts/NotoSans/NotoSans-Regular.ttf").to_vec(),
-        )
-        .unwrap();
-        ui.fonts.insert(font_collection.into_font().unwrap());
-
+        // without italic faces the upright faces are used for italic text
+        let regular = ui.fonts.insert(faces.regular);
+        let bold = ui.fonts.insert(faces.bold);
+        let italic = faces
+            .italic
+            .map_or(regular, |italic| ui.fonts.insert(italic));
+        let bold_italic = faces
+            .bold_italic
+            .map_or(bold, |bold_italic| ui.fonts.insert(bold_italic));
         ui.keyboard_capture(ids.input);
 
         MenuApp {
-            state: AppState {
-                items: vec![],
-                selected: 0,
-                search: String::from(""),
-            },
+            controller,
             ids,
             ui,
-            title: title.to_string(),
+            fonts: Fonts {
+                regular,
+                bold,
+                italic,
+                bold_italic,
+                fallback: Vec::new(),
+            },
+            fallback_fonts,
+            layout,
+            placement: builder.placement,
+            monitor_selection: builder.monitor,
+            icon_theme: builder.icon_theme,
+            search_mode: builder.search_mode,
+            title: builder.title,
         }
     }
 
+    /// Set the items to show, these are filtered by the built in fuzzy matcher when using `run`
     pub fn set_items(&mut self, items: Vec<Item>) {
-        self.state.items = items;
-        self.state.selected = 0;
+        self.controller.set_items(items);
+    }
+
+    /// Set the freedesktop icon theme used to look up icons by name
+    pub fn set_icon_theme(&mut self, theme: &str) {
+        self.icon_theme = theme.to_string();
     }
 
-    pub fn main_loop<Search>(self, search: Search) -> Option<Item>
+    /// Set how the search callback is run, either debounced on a background thread or
+    /// immediately on the ui thread
+    pub fn set_search_mode(&mut self, search_mode: SearchMode) {
+        self.search_mode = search_mode;
+    }
+
+    /// Set the keymap used to map key presses to actions
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        self.controller.set_keymap(keymap);
+    }
+
+    /// Render the menu as it would be shown into an image, without opening a window
+    ///
+    /// Useful for snapshot tests and screenshots.
+    pub fn render_to_image(&mut self) -> RgbaImage {
+        let mut image_map = conrod_core::image::Map::new();
+        let mut icons = IconCache::new(&self.icon_theme, ICON_SIZE, None);
+
+        // lay out the gui until the height of the menu and the icons are settled
+        loop {
+            let update = self.update_gui(&mut icons);
+            let loaded = icons.wait_loaded(|icon| image_map.insert(icon));
+            let resized = update.height as f64 != self.ui.win_h;
+            if resized {
+                let width = self.ui.win_w;
+                self.ui.handle_event(conrod_core::event::Input::Resize(
+                    width,
+                    update.height as f64,
+                ));
+            }
+            if !loaded && !resized {
+                break;
+            }
+        }
+
+        let mut rasterizer = Rasterizer::new(self.ui.win_w, self.ui.win_h, 1.0);
+        rasterizer.draw(self.ui.draw(), &image_map);
+        rasterizer.into_image()
+    }
+
+    /// Show the menu for the items set with `set_items`, filtered by the built in fuzzy matcher
+    pub fn run(self) -> Result<Outcome<Item>, Error> {
+        self.run_loop(|_, _| SearchBackend::Static)
+    }
+
+    /// Show the menu, using the search callback to get the items for every query
+    pub fn main_loop<Search>(self, search: Search) -> Result<Outcome<Item>, Error>
     where
         Search: Fn(String) -> Vec<Item> + Send + 'static,
     {
-        let MenuApp {
-            mut state,
-            ids,
-            mut ui,
-            title,
-        } = self;
+        self.main_loop_streaming(move |query, _token, sink| {
+            sink.send(search(query));
+        })
+    }
 
-        let mut event_loop = EventLoop::new();
+    /// Show the menu, using a search callback that sends its results to the sink in batches
+    ///
+    /// Batches are shown as soon as they are send, once the query changes the token for the
+    /// running search is cancelled and any batches still send for it are ignored.
+    pub fn main_loop_streaming<Search>(self, search: Search) -> Result<Outcome<Item>, Error>
+    where
+        Search: Fn(String, &CancellationToken, &ResultSink<Item>) + Send + 'static,
+    {
+        self.run_loop(move |event_proxy, search_mode| match search_mode {
+            SearchMode::Debounced(debounce) => {
+                SearchBackend::Worker(SearchWorker::spawn(search, event_proxy, debounce))
+            }
+            SearchMode::Immediate => {
+                SearchBackend::Immediate(Box::new(move |query| search_immediate(&search, query)))
+            }
+        })
+    }
+
+    /// Show the menu, using an async search callback to get the items for every query
+    ///
+    /// The future is polled on an executor in a background thread and dropped when the query
+    /// changes before it's finished.
+    pub fn main_loop_async<Search, Fut>(self, search: Search) -> Result<Outcome<Item>, Error>
+    where
+        Search: Fn(String) -> Fut + Send + 'static,
+        Fut: Future<Output = Vec<Item>> + 'static,
+    {
+        self.main_loop_stream(move |query| search(query).map(stream::iter).flatten_stream())
+    }
+
+    /// Show the menu, using a search callback that returns a stream of items for every query
+    ///
+    /// The stream is polled on an executor in a background thread, items are shown as soon as
+    /// they are available and the stream is dropped when the query changes.
+    pub fn main_loop_stream<Search, S>(self, search: Search) -> Result<Outcome<Item>, Error>
+    where
+        Search: Fn(String) -> S + Send + 'static,
+        S: Stream<Item = Item> + 'static,
+    {
+        self.run_loop(move |event_proxy, search_mode| match search_mode {
+            SearchMode::Debounced(debounce) => {
+                SearchBackend::Worker(SearchWorker::spawn_stream(search, event_proxy, debounce))
+            }
+            SearchMode::Immediate => SearchBackend::Immediate(Box::new(move |query| {
+                without_positions(block_on(search(query).collect::<Vec<_>>()))
+            })),
+        })
+    }
+
+    fn run_loop<Backend>(mut self, backend: Backend) -> Result<Outcome<Item>, Error>
+    where
+        Backend: FnOnce(Option<EventLoopProxy<()>>, SearchMode) -> SearchBackend<Item>,
+    {
+        // on Wayland the layer shell lets the menu place itself and grab the keyboard, which
+        // a normal window can't
+        #[cfg(all(feature = "layer-shell", unix, not(target_os = "macos")))]
+        {
+            if let Some(layer_shell) = LayerShell::connect() {
+                let backend = backend(None, self.search_mode);
+                return layer_shell.run(self, backend);
+            }
+        }
+
+        // without a gpu the window is drawn in software, which needs X11 or Windows
+        let adapter = request_adapter();
+        let mut event_loop = match adapter {
+            Some(_) => EventLoop::new(),
+            None => software_event_loop()?,
+        };
 
         let window = winit::window::WindowBuilder::new()
-            .with_title(&title)
+            .with_title(&self.title)
             .with_inner_size(winit::dpi::LogicalSize {
-                width: ui.win_w,
-                height: ui.win_h,
+                width: self.ui.win_w,
+                height: self.ui.win_h,
             })
             .with_visible(false)
-            .build(&event_loop)
-            .unwrap();
-        let mut renderer = Renderer::new(&window);
+            .build(&event_loop)?;
+        let mut renderer = WindowRenderer::new(&window, adapter)?;
         let mut visible = false;
 
-        let image_map = conrod_core::image::Map::new();
-
-        let mut result = None;
-
-        let (query_tx, query_rx) = channel();
-        let (items_tx, items_rx) = channel();
+        let mut modifiers = ModifiersState::empty();
 
         let event_proxy = event_loop.create_proxy();
+        let mut icons = IconCache::new(&self.icon_theme, ICON_SIZE, Some(event_proxy.clone()));
 
-        std::thread::spawn(move || {
-            // first block for the first query
-            while let Ok(mut query) = query_rx.recv() {
-                // then wait until there is no new query set for some duration
-                while let Ok(new_query) = query_rx.recv_timeout(Duration::from_millis(100)) {
-                    query = new_query;
-                }
-
-                if let Err(_) = items_tx.send(search(query)) {
-                    break;
-                }
-
-                // wakeup the event loop
-                let _ = event_proxy.send_event(());
-            }
-        });
+        let mut backend = backend(Some(event_proxy), self.search_mode);
+        self.start(&mut backend);
 
         let mut state_updated = false;
+        let mut error = None;
 
         event_loop.run_return(|event, _, control_flow| {
             if let Some(event) = convert_event(&event, &window) {
-                ui.handle_event(event);
+                self.ui.handle_event(event);
             }
 
             *control_flow = if cfg!(feature = "metal-auto-capture") {
@@ -144,47 +319,66 @@ impl<Item: Display + Send + 'static> MenuApp<Item> {
                 ControlFlow::Wait
             };
 
-            if let Ok(items) = items_rx.try_recv() {
-                state.items = items;
-                state_updated = true
-            };
+            if self.receive_results(&mut backend) {
+                state_updated = true;
+            }
+            // draw the icons loaded in the background
+            if icons.upload_loaded(|icon| renderer.upload_image(icon)) {
+                state_updated = true;
+            }
 
-            match event {
-                Event::MainEventsCleared => {
-                    // Update widgets if any event has happened
-                    if ui.global_input().events().next().is_some() || state_updated {
-                        state_updated = false;
-                        let mut ui = ui.set_widgets();
-                        let (height, event) = gui(&mut ui, &ids, &mut state);
-                        if let AppEvent::Search(query) = event {
-                            if let Err(e) = query_tx.send(query) {
-                                eprintln!("{}", e);
-                            }
-                        }
+            let mut input = None;
 
-                        window.set_inner_size(LogicalSize::new(
-                            window.inner_size().to_logical(window.scale_factor()).width,
-                            height,
-                        ));
-                        window.request_redraw();
-                    }
+            match event {
+                // Update widgets if any event has happened
+                Event::MainEventsCleared
+                    if self.ui.global_input().events().next().is_some() || state_updated =>
+                {
+                    state_updated = false;
+                    let update = self.update_gui(&mut icons);
+                    input = update.query.map(Input::Query);
+
+                    window.set_inner_size(LogicalSize::new(
+                        window.inner_size().to_logical(window.scale_factor()).width,
+                        update.height,
+                    ));
+                    window.request_redraw();
                 }
                 Event::RedrawRequested(_) => {
-                    if let Some(primitives) = ui.draw_if_changed() {
-                        renderer.render(primitives, &window, &image_map);
+                    if let Some(primitives) = self.ui.draw_if_changed() {
+                        if let Err(e) = renderer.render(primitives, &window) {
+                            error = Some(e);
+                            *control_flow = ControlFlow::Exit;
+                        }
                     }
 
                     if !visible {
+                        // when opening at the cursor the window goes on the monitor under it
+                        let placement = &self.placement;
+                        let cursor = match placement.at_cursor {
+                            true => cursor_position(&window),
+                            false => None,
+                        };
+                        let monitor = cursor
+                            .and_then(|cursor| monitor_containing(&window, cursor))
+                            .unwrap_or_else(|| self.monitor_selection.select(&window));
+
+                        // the window is sized for the monitor it was created on, which can have
+                        // another scale factor than the one it's moved to
+                        let scale_factor = monitor.scale_factor();
+                        let mut size = window.inner_size().to_logical::<f64>(window.scale_factor());
+                        if let Some(width) = placement.window_width(monitor.size()) {
+                            size.width = width as f64 / scale_factor;
+                        }
                         window.set_visible(true);
-                        let monitor = window.primary_monitor();
-                        let (x_pos, y_pos): (i32, i32) = monitor.position().into();
-                        let (x_size, y_size): (u32, u32) = monitor.size().into();
-
-                        let size = window.inner_size();
-                        window.set_outer_position(PhysicalPosition::new(
-                            x_pos + (x_size - size.width) as i32 / 2,
-                            y_pos + (y_size - size.height) as i32 / 3,
+                        window.set_outer_position(placement.position(
+                            monitor.position(),
+                            monitor.size(),
+                            scale_factor,
+                            size.to_physical(scale_factor),
+                            cursor,
                         ));
+                        window.set_inner_size(size);
                         window.set_decorations(false);
                         visible = true;
                     }
@@ -193,40 +387,16 @@ impl<Item: Display + Send + 'static> MenuApp<Item> {
                     WindowEvent::KeyboardInput {
                         input:
                             KeyboardInput {
-                                virtual_keycode,
+                                virtual_keycode: Some(key),
                                 state: ElementState::Pressed,
                                 ..
                             },
                         ..
-                    } => match virtual_keycode {
-                        Some(VirtualKeyCode::Return) => {
-                            result = if state.items.len() > state.selected {
-                                Some(state.items.remove(state.selected))
-                            } else {
-                                None
-                            };
-                            *control_flow = ControlFlow::Exit;
-                        }
-                        Some(VirtualKeyCode::Escape) => *control_flow = ControlFlow::Exit,
-                        Some(VirtualKeyCode::Up) => {
-                            state.selected = state.selected.saturating_sub(1);
-                            state_updated = true;
-                        }
-                        Some(VirtualKeyCode::Down) => {
-                            state.selected =
-                                min(state.selected + 1, state.items.len().saturating_sub(1));
-                            state_updated = true;
-                        }
-                        _ => {}
-                    },
+                    } => input = Some(Input::Key(key, modifiers)),
                     WindowEvent::CloseRequested | WindowEvent::Destroyed => {
-                        *control_flow = ControlFlow::Exit
-                    }
-                    WindowEvent::Focused(focused) => {
-                        if !focused {
-                            *control_flow = ControlFlow::Exit;
-                        }
+                        input = Some(Input::Close)
                     }
+                    WindowEvent::Focused(false) => input = Some(Input::FocusLost),
                     WindowEvent::Resized(new_size) => {
                         renderer.resize(new_size);
                     }
@@ -235,13 +405,171 @@ impl<Item: Display + Send + 'static> MenuApp<Item> {
                     }
                     _ => {}
                 },
+                Event::DeviceEvent {
+                    event: DeviceEvent::ModifiersChanged(new_modifiers),
+                    ..
+                } => modifiers = new_modifiers,
                 _ => {}
             }
+
+            if let Some(input) = input {
+                match self.handle_input(&mut backend, input) {
+                    // draw the new results
+                    AppEvent::Search(_) => *control_flow = ControlFlow::Poll,
+                    AppEvent::Continue => {}
+                    AppEvent::Exit => *control_flow = ControlFlow::Exit,
+                }
+                state_updated = true;
+            }
         });
 
         window.set_visible(false);
 
-        result
+        match error {
+            Some(error) => Err(error),
+            None => Ok(self.controller.into_outcome()),
+        }
+    }
+
+    /// Prepare the controller for the search backend and search for the initial query
+    pub(crate) fn start(&mut self, backend: &mut SearchBackend<Item>) {
+        self.controller
+            .set_filter_items(matches!(backend, SearchBackend::Static));
+        let query = self.controller.state().search().to_string();
+        if !query.is_empty() {
+            start_search(backend, &mut self.controller, query);
+        }
+    }
+
+    /// Take the results of the search running in the background, returns whether there were
+    /// any
+    pub(crate) fn receive_results(&mut self, backend: &mut SearchBackend<Item>) -> bool {
+        let mut received = false;
+        while let Some(message) = backend.try_recv() {
+            self.controller.handle_search_message(message);
+            received = true;
+        }
+        received
+    }
+
+    /// Lay out the gui for the current state, loading the fallback fonts and requesting the
+    /// icons it needs
+    pub(crate) fn update_gui(&mut self, icons: &mut IconCache) -> GuiUpdate {
+        load_fallback_fonts(
+            &mut self.fallback_fonts,
+            &mut self.fonts,
+            &mut self.ui,
+            &self.layout,
+            self.controller.state(),
+        );
+        let (height, event) = gui(
+            &mut self.ui.set_widgets(),
+            &self.ids,
+            &self.fonts,
+            &self.layout,
+            icons,
+            self.controller.state_mut(),
+        );
+        GuiUpdate {
+            height,
+            query: match event {
+                AppEvent::Search(query) => Some(query),
+                _ => None,
+            },
+        }
+    }
+
+    /// Pass the input to the controller and start searching when the query changed
+    pub(crate) fn handle_input(
+        &mut self,
+        backend: &mut SearchBackend<Item>,
+        input: Input,
+    ) -> AppEvent {
+        let from_key = matches!(input, Input::Key(..));
+        let event = self.controller.handle_input(input);
+        if let AppEvent::Search(query) = &event {
+            // a key binding like completion replaced the query, keep typing after it
+            if from_key {
+                move_cursor_to_end(&mut self.ui);
+            }
+            start_search(backend, &mut self.controller, query.clone());
+        }
+        event
+    }
+}
+
+/// The outcome of laying out the gui
+pub(crate) struct GuiUpdate {
+    /// The height the menu needs
+    pub height: u32,
+    /// The new query when the search input was edited
+    pub query: Option<String>,
+}
+
+/// An event loop that creates X11 windows, for drawing in software
+///
+/// On Wayland this goes through XWayland, the layer shell is drawn in software by itself.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn software_event_loop() -> Result<EventLoop<()>, Error> {
+    use winit::platform::unix::EventLoopExtUnix;
+
+    EventLoop::new_x11().map_err(|_| Error::NoRenderer)
+}
+
+#[cfg(windows)]
+fn software_event_loop() -> Result<EventLoop<()>, Error> {
+    Ok(EventLoop::new())
+}
+
+#[cfg(not(any(windows, all(unix, not(target_os = "macos")))))]
+fn software_event_loop() -> Result<EventLoop<()>, Error> {
+    Err(Error::NoRenderer)
+}
+
+/// Load the fallback fonts for the characters of the search and the shown results that the
+/// loaded fonts can't draw
+fn load_fallback_fonts<Item: MenuItem>(
+    fallback_fonts: &mut FallbackFonts,
+    fonts: &mut Fonts,
+    ui: &mut Ui,
+    layout: &Layout,
+    app: &AppState<Item>,
+) {
+    fallback_fonts.cover(app.search(), fonts, &mut ui.fonts);
+    for item in app.results().skip(app.scroll()).take(layout.max_rows) {
+        fallback_fonts.cover(&item.title(), fonts, &mut ui.fonts);
+        if let Some(subtitle) = item.subtitle() {
+            fallback_fonts.cover(&subtitle, fonts, &mut ui.fonts);
+        }
+    }
+}
+
+/// Move the cursor of the search input after the text, for when a key binding like completion
+/// replaced the query
+fn move_cursor_to_end(ui: &mut Ui) {
+    use conrod_core::event::Input;
+    use conrod_core::input::{Button, Key};
+
+    ui.handle_event(Input::Press(Button::Keyboard(Key::End)));
+    ui.handle_event(Input::Release(Button::Keyboard(Key::End)));
+}
+
+/// Start searching for a new query
+///
+/// The static items are already filtered by the controller itself
+fn start_search<Item: MenuItem + Send + 'static>(
+    backend: &mut SearchBackend<Item>,
+    controller: &mut MenuController<Item>,
+    query: String,
+) {
+    match backend {
+        SearchBackend::Worker(worker) => match worker.search(query) {
+            Ok(()) => controller.expect_results(),
+            // the worker stopped, no results will come for the query
+            Err(_) => controller.set_search_results(Vec::new()),
+        },
+        SearchBackend::Immediate(search) => controller.set_search_results(search(query)),
+        SearchBackend::Static => {}
     }
 }
 
@@ -277,71 +605,133 @@ widget_ids! {
         canvas,
         // the list of results
         items,
+        // position indicator for long lists
+        scrollbar,
     }
 }
 
 /// Instantiate a GUI demonstrating every widget available in conrod.
-pub fn gui<Item: Display>(
+pub fn gui<Item: MenuItem>(
     ui: &mut conrod_core::UiCell,
     ids: &Ids,
+    fonts: &Fonts,
+    layout: &Layout,
+    icons: &mut IconCache,
     app: &mut AppState<Item>,
 ) -> (u32, AppEvent) {
-    use conrod_core::{widget, Colorable, Labelable, Positionable, Widget};
-
-    const MARGIN: conrod_core::Scalar = 2.0;
-    const SUBTITLE_SIZE: conrod_core::FontSize = 16;
-
-    let item_size = SUBTITLE_SIZE + 2;
-    let height = item_size * (app.items.len() as u32 + 1) + 4;
+    use conrod_core::{widget, Colorable, Positionable, Widget};
+
+    // use two line rows for all items if any item has a subtitle
+    let two_line = app.any_subtitle;
+    // and align all items if any item has an icon
+    let icon_column = app.any_icon;
+    let item_size = match (layout.row_height, two_line) {
+        (Some(height), _) => height,
+        (None, true) => layout.font_size + layout.subtitle_font_size + 4,
+        (None, false) => layout.font_size + 2,
+    };
+    let visible_rows = min(app.matches.len(), layout.max_rows);
+    let list_rows = match layout.fixed_height {
+        true => layout.max_rows,
+        false => visible_rows,
+    };
+    let list_height = item_size * list_rows as u32;
+    let height = layout.input_height() + list_height + (2.0 * layout.inset()).ceil() as u32;
+
+    // only the rows from the scroll offset are given to the list, so the scroll position stays
+    // under our control and can follow the selection, and the layout cost doesn't grow with
+    // the number of results
+    app.visible_rows = visible_rows;
+    app.scroll = min(app.scroll, app.matches.len().saturating_sub(visible_rows));
 
     widget::Canvas::new()
-        .pad(MARGIN)
+        .pad(layout.inset())
         .scroll_kids_vertically()
-        .border(1.0)
-        .border_color(ui.theme.label_color)
+        .border(layout.border_width)
+        .border_color(layout.border_color.unwrap_or(ui.theme.label_color))
         .h(height as f64)
         .set(ids.canvas, ui);
 
-    let search = widget::TextEdit::new(&app.search)
-        .font_size(SUBTITLE_SIZE)
+    // scrolling the mouse wheel over the menu scrolls the list
+    let scrolled: f64 = ui
+        .widget_input(ids.canvas)
+        .scrolls()
+        .map(|scroll| scroll.y)
+        .sum();
+    if scrolled != 0.0 {
+        // scroll at least one row for small touchpad movements
+        let rows = match (scrolled / item_size as f64).round() as isize {
+            0 => scrolled.signum() as isize,
+            rows => rows,
+        };
+        app.scroll_by(rows);
+    }
+
+    let search = widget::TextEdit::new(app.search())
+        .font_size(layout.font_size)
         .kid_area_w_of(ids.canvas)
         .mid_top()
-        .h(SUBTITLE_SIZE as f64 + 1.0)
+        .h(layout.font_size as f64 + 1.0)
         .set(ids.input, ui);
 
-    let (mut events, scrollbar) = widget::ListSelect::single(app.items.len())
+    let (mut events, _) = widget::ListSelect::single(visible_rows)
         .flow_down()
-        .item_size(SUBTITLE_SIZE as f64 + 2.0)
-        .scrollbar_next_to()
-        .h(item_size as f64 * (app.items.len() as f64))
+        .item_size(item_size as f64)
+        .h(list_height as f64)
         .mid_bottom()
         .kid_area_w_of(ids.canvas)
         .set(ids.items, ui);
 
     // Handle the `ListSelect`s events.
-    while let Some(event) = events.next(ui, |i| i == app.selected) {
+    let scroll = app.scroll;
+    while let Some(event) = events.next(ui, |i| scroll + i == app.selected) {
         use conrod_core::widget::list_select::Event;
         match event {
             // For the `Item` events we instantiate the `List`'s items.
             Event::Item(item) => {
-                let label = app.items[item.i].to_string();
-                let color = match item.i == app.selected {
+                let row_index = scroll + item.i;
+                let matched = &app.matches[row_index];
+                let menu_item = &app.items[matched.index];
+                let title = menu_item.title();
+                let subtitle = menu_item.subtitle();
+                // the markup is only used when its text lines up with the highlighted title
+                let markup = menu_item
+                    .markup()
+                    .and_then(|text| parse_markup(&text))
+                    .filter(|spans| {
+                        let text = spans.iter().flat_map(|span| span.text.chars());
+                        text.eq(title.chars())
+                    });
+                let color = match row_index == app.selected {
                     true => ui.theme.shape_color,
                     false => ui.theme.background_color,
                 };
-                let button = widget::Button::new()
-                    .border(0.0)
+                let mut row = ItemRow::new(&title, &matched.positions)
                     .color(color)
-                    .label(&label)
-                    .left_justify_label()
-                    .label_x(Relative::Place(Place::Start(None)))
-                    .label_font_size(SUBTITLE_SIZE);
-                item.set(button, ui);
+                    .font_id(fonts.regular)
+                    .bold_font_id(fonts.bold)
+                    .italic_font_id(fonts.italic)
+                    .bold_italic_font_id(fonts.bold_italic)
+                    .fallback_fonts(&fonts.fallback)
+                    .font_size(layout.font_size)
+                    .subtitle_font_size(layout.subtitle_font_size)
+                    .icon_column(icon_column)
+                    .marked(app.is_marked(row_index));
+                if let Some(markup) = &markup {
+                    row = row.markup(markup);
+                }
+                if let Some(subtitle) = &subtitle {
+                    row = row.subtitle(subtitle);
+                }
+                if let Some(icon) = menu_item.icon().and_then(|icon| icons.get(icon)) {
+                    row = row.icon(icon);
+                }
+                item.set(row, ui);
             }
 
             // The selection has changed.
             Event::Selection(selection) => {
-                app.selected = selection;
+                app.selected = scroll + selection;
             }
 
             // The remaining events indicate interactions with the `ListSelect` widget.
@@ -349,18 +739,21 @@ pub fn gui<Item: Display>(
         }
     }
 
-    // Instantiate the scrollbar for the list.
-    if let Some(s) = scrollbar {
-        s.set(ui);
+    // show where we are in the results when they don't all fit
+    if app.matches.len() > visible_rows {
+        let total = app.matches.len() as f64;
+        let thumb_height = list_height as f64 * visible_rows as f64 / total;
+        let thumb_offset = list_height as f64 * scroll as f64 / total;
+        widget::Rectangle::fill([SCROLLBAR_WIDTH, thumb_height])
+            .top_right_with_margins_on(ids.items, thumb_offset, 0.0)
+            .color(ui.theme.label_color.alpha(0.5))
+            .set(ids.scrollbar, ui);
     }
 
     (
         height,
         match search {
-            Some(search) => {
-                app.set_search(search.clone());
-                AppEvent::Search(search)
-            }
+            Some(search) => AppEvent::Search(search),
             None => AppEvent::Continue,
         },
     )
@@ -0,0 +1,143 @@
+use rufi::{Config, MarkupItem, MenuAppBuilder, MenuItem, MonitorSelection, Outcome, ThemeConfig};
+use std::io::{self, BufRead, Write};
+use std::process::exit;
+
+pub const WIN_W: u32 = 600;
+
+/// dmenu compatible launcher, reads the items from stdin and prints the selected one to stdout
+fn main() {
+    let mut title = String::from("rufi");
+    let mut multi_select = false;
+    let mut cycle = false;
+    let mut max_lines = None;
+    let mut fixed_num_lines = false;
+    let mut filter = String::new();
+    let mut screenshot = None;
+    let mut markup_rows = false;
+    let mut monitor: Option<MonitorSelection> = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-p" => {
+                if let Some(prompt) = args.next() {
+                    title = prompt;
+                }
+            }
+            "-multi-select" => multi_select = true,
+            "-cycle" => cycle = true,
+            "-l" => max_lines = args.next().and_then(|lines| lines.parse().ok()),
+            "-fixed-num-lines" => fixed_num_lines = true,
+            "-filter" => filter = args.next().unwrap_or_default(),
+            "-screenshot" => screenshot = args.next(),
+            "-markup-rows" => markup_rows = true,
+            "-m" => monitor = args.next().and_then(|monitor| monitor.parse().ok()),
+            _ => {}
+        }
+    }
+
+    let stdin = io::stdin();
+    let lines: Vec<String> = stdin
+        .lock()
+        .lines()
+        .filter_map(Result::ok)
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    };
+    let theme = match ThemeConfig::load() {
+        Ok(theme) => theme,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    };
+
+    let mut builder = MenuAppBuilder::new()
+        .config(&config)
+        .theme_config(&theme)
+        .width(WIN_W)
+        .title(&title)
+        .multi_select(multi_select)
+        .wrap_around(cycle)
+        .fixed_height(fixed_num_lines)
+        .query(&filter);
+    if let Some(max_lines) = max_lines {
+        builder = builder.max_rows(max_lines);
+    }
+    if let Some(monitor) = monitor {
+        builder = builder.monitor(monitor);
+    }
+
+    // with markup the lines are printed as they were read, including the markup
+    match markup_rows {
+        true => run(
+            builder,
+            lines.iter().map(|line| MarkupItem::new(line)).collect(),
+            screenshot,
+            MarkupItem::into_markup,
+        ),
+        false => run(builder, lines, screenshot, |line| line),
+    }
+}
+
+/// Show the menu and print the selected lines, or save a screenshot of the menu to `screenshot`
+fn run<Item: MenuItem + Send + 'static>(
+    builder: MenuAppBuilder,
+    items: Vec<Item>,
+    screenshot: Option<String>,
+    to_line: impl Fn(Item) -> String,
+) {
+    let mut app = match builder.build::<Item>() {
+        Ok(app) => app,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    };
+    app.set_items(items);
+
+    // render the menu to a png instead of showing it
+    if let Some(path) = screenshot {
+        if let Err(e) = app.render_to_image().save(&path) {
+            eprintln!("{}", e);
+            exit(1);
+        }
+        exit(0);
+    }
+
+    let result = match app.run() {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    };
+
+    // like rofi, custom keybindings exit with 10 and up
+    let (lines, code) = match result {
+        Outcome::Selected(item, _) => (vec![to_line(item)], 0),
+        Outcome::Marked(marked) => (
+            marked.into_iter().map(|(item, _)| to_line(item)).collect(),
+            0,
+        ),
+        Outcome::Custom(query) => (vec![query], 0),
+        Outcome::CustomKey(index, item) => {
+            (item.into_iter().map(&to_line).collect(), 9 + index as i32)
+        }
+        Outcome::Cancelled | Outcome::FocusLost => exit(1),
+    };
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for line in lines {
+        let _ = writeln!(stdout, "{}", line);
+    }
+    let _ = stdout.flush();
+    exit(code);
+}
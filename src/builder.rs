@@ -0,0 +1,480 @@
+use crate::app::{default_theme, Layout, MenuApp};
+use crate::config::Config;
+use crate::controller::{AppState, MenuController};
+use crate::font::{load_font, FallbackFonts, FontFaces, FontLookup, FontSource, FontStyle};
+use crate::item::MenuItem;
+use crate::keys::{Action, KeyCombo, Keymap};
+use crate::monitor::MonitorSelection;
+use crate::placement::{Anchor, Offset, Placement};
+use crate::row;
+use crate::search::SearchMode;
+use crate::theme::ThemeConfig;
+use conrod_core::theme::WidgetDefault;
+use conrod_core::{Color, FontSize, Scalar, Theme};
+use std::any::TypeId;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Invalid configuration passed to the `MenuAppBuilder`
+#[derive(Debug)]
+pub enum BuildError {
+    /// The window width is zero
+    InvalidWidth,
+    /// One of the font sizes is zero
+    InvalidFontSize,
+    /// The maximum number of rows is zero
+    InvalidMaxRows,
+    /// The placement is outside of the monitor
+    InvalidPlacement(Placement),
+    /// The font data couldn't be loaded
+    InvalidFont(rusttype::Error),
+    /// No font of the family is installed
+    FontNotFound(String),
+    /// The font file couldn't be read
+    FontFile(PathBuf, io::Error),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::InvalidWidth => write!(f, "window width must be larger than zero"),
+            BuildError::InvalidFontSize => write!(f, "font sizes must be larger than zero"),
+            BuildError::InvalidMaxRows => write!(f, "maximum number of rows must be at least one"),
+            BuildError::InvalidPlacement(placement) => write!(
+                f,
+                "placement ({}, {}) must be between 0 and 1",
+                placement.x, placement.y
+            ),
+            BuildError::InvalidFont(err) => write!(f, "invalid font: {}", err),
+            BuildError::FontNotFound(family) => write!(f, "font not found: {}", family),
+            BuildError::FontFile(path, err) => {
+                write!(f, "can't read font {}: {}", path.display(), err)
+            }
+        }
+    }
+}
+
+impl Error for BuildError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BuildError::InvalidFont(err) => Some(err),
+            BuildError::FontFile(_, err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Builder for configuring a `MenuApp`
+pub struct MenuAppBuilder {
+    pub(crate) width: u32,
+    pub(crate) title: String,
+    pub(crate) theme: Theme,
+    font: Option<FontSource>,
+    bold_font: Option<FontSource>,
+    fallback_fonts: Vec<FontSource>,
+    font_size: FontSize,
+    subtitle_font_size: FontSize,
+    max_rows: usize,
+    fixed_height: bool,
+    margin: Scalar,
+    border_width: Scalar,
+    border_color: Option<Color>,
+    row_height: Option<u32>,
+    pub(crate) placement: Placement,
+    pub(crate) monitor: MonitorSelection,
+    pub(crate) query: String,
+    pub(crate) icon_theme: String,
+    pub(crate) search_mode: SearchMode,
+    pub(crate) multi_select: bool,
+    pub(crate) wrap_around: bool,
+    pub(crate) keymap: Keymap,
+}
+
+impl Default for MenuAppBuilder {
+    fn default() -> Self {
+        MenuAppBuilder {
+            width: 600,
+            title: String::from("rufi"),
+            theme: default_theme(),
+            font: None,
+            bold_font: None,
+            fallback_fonts: FontSource::default_fallback(),
+            font_size: 16,
+            subtitle_font_size: 12,
+            max_rows: 15,
+            fixed_height: false,
+            margin: 2.0,
+            border_width: 1.0,
+            border_color: None,
+            row_height: None,
+            placement: Placement::default(),
+            monitor: MonitorSelection::default(),
+            query: String::new(),
+            icon_theme: String::from("hicolor"),
+            search_mode: SearchMode::default(),
+            multi_select: false,
+            wrap_around: false,
+            keymap: Keymap::default(),
+        }
+    }
+}
+
+impl MenuAppBuilder {
+    pub fn new() -> Self {
+        MenuAppBuilder::default()
+    }
+
+    /// Width of the window in logical pixels
+    pub fn width(mut self, width: u32) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    /// The conrod theme used for the colors of the menu
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Font data (ttf or otf) to use instead of the bundled NotoSans
+    pub fn font(mut self, font: Vec<u8>) -> Self {
+        self.font = Some(FontSource::Data(font));
+        self
+    }
+
+    /// Installed font family to use instead of the bundled NotoSans, the bold face of the family
+    /// is used for the bold text unless `bold_font` is set
+    pub fn font_family(mut self, family: &str) -> Self {
+        self.font = Some(FontSource::family(family));
+        self
+    }
+
+    /// Font data (ttf or otf) for the bold text, defaults to the bundled NotoSans Bold
+    pub fn bold_font(mut self, font: Vec<u8>) -> Self {
+        self.bold_font = Some(FontSource::Data(font));
+        self
+    }
+
+    /// Fonts used in order for the characters missing from the main font, fallback families
+    /// that aren't installed are skipped
+    ///
+    /// Defaults to a few common Noto and DejaVu families covering CJK, symbols and emoji.
+    pub fn fallback_fonts(mut self, fonts: Vec<FontSource>) -> Self {
+        self.fallback_fonts = fonts;
+        self
+    }
+
+    /// Font size for the search input and item titles
+    pub fn font_size(mut self, size: FontSize) -> Self {
+        self.font_size = size;
+        self
+    }
+
+    /// Font size for the item subtitles
+    pub fn subtitle_font_size(mut self, size: FontSize) -> Self {
+        self.subtitle_font_size = size;
+        self
+    }
+
+    /// Maximum number of rows to show at once, longer result lists can be scrolled
+    pub fn max_rows(mut self, rows: usize) -> Self {
+        self.max_rows = rows;
+        self
+    }
+
+    /// Keep the window at the height for `max_rows` rows instead of shrinking it to the results
+    pub fn fixed_height(mut self, fixed_height: bool) -> Self {
+        self.fixed_height = fixed_height;
+        self
+    }
+
+    /// Space between the edge of the window and the content, the border is drawn inside of it
+    pub fn margin(mut self, margin: Scalar) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Width of the border around the window
+    pub fn border_width(mut self, width: Scalar) -> Self {
+        self.border_width = width;
+        self
+    }
+
+    /// Color of the border around the window, defaults to the text color
+    pub fn border_color(mut self, color: Color) -> Self {
+        self.border_color = Some(color);
+        self
+    }
+
+    /// Height of the result rows, defaults to fitting the title and subtitle
+    pub fn row_height(mut self, height: u32) -> Self {
+        self.row_height = Some(height);
+        self
+    }
+
+    pub fn placement(mut self, placement: Placement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    /// The monitor to open the menu on, when opening at the cursor the monitor with the cursor
+    /// is always used
+    pub fn monitor(mut self, monitor: MonitorSelection) -> Self {
+        self.monitor = monitor;
+        self
+    }
+
+    /// Place the window at one of the edges, corners or the center of the monitor, keeping the
+    /// offsets and other placement options
+    pub fn anchor(mut self, anchor: Anchor) -> Self {
+        self.placement = Placement::anchored(anchor)
+            .offset(self.placement.offset_x, self.placement.offset_y)
+            .full_width(self.placement.full_width)
+            .at_cursor(self.placement.at_cursor);
+        self
+    }
+
+    /// Move the window from its anchored position, in logical pixels or percent of the monitor
+    pub fn offset(mut self, x: Offset, y: Offset) -> Self {
+        self.placement = self.placement.offset(x, y);
+        self
+    }
+
+    /// Stretch the window over the full width of the monitor like dmenu, instead of `width`
+    pub fn full_width(mut self, full_width: bool) -> Self {
+        self.placement = self.placement.full_width(full_width);
+        self
+    }
+
+    /// Open the window at the mouse cursor, only supported on X11
+    pub fn at_cursor(mut self, at_cursor: bool) -> Self {
+        self.placement = self.placement.at_cursor(at_cursor);
+        self
+    }
+
+    /// The query the search input starts with
+    pub fn query(mut self, query: &str) -> Self {
+        self.query = query.to_string();
+        self
+    }
+
+    /// The freedesktop icon theme used to look up icons by name
+    pub fn icon_theme(mut self, theme: &str) -> Self {
+        self.icon_theme = theme.to_string();
+        self
+    }
+
+    pub fn search_mode(mut self, search_mode: SearchMode) -> Self {
+        self.search_mode = search_mode;
+        self
+    }
+
+    /// Allow marking multiple items with shift+tab or ctrl+space, which are then returned
+    /// together as `Outcome::Marked`
+    pub fn multi_select(mut self, multi_select: bool) -> Self {
+        self.multi_select = multi_select;
+        self
+    }
+
+    /// Move from the last row to the first when moving down and the other way around
+    pub fn wrap_around(mut self, wrap_around: bool) -> Self {
+        self.wrap_around = wrap_around;
+        self
+    }
+
+    /// Replace the keymap, the default keymap binds the arrow keys and the emacs and vi style
+    /// movement keys
+    pub fn keymap(mut self, keymap: Keymap) -> Self {
+        self.keymap = keymap;
+        self
+    }
+
+    /// Bind a key to an action, replacing any existing binding for the key
+    pub fn bind(mut self, combo: KeyCombo, action: Action) -> Self {
+        self.keymap.bind(combo, action);
+        self
+    }
+
+    /// Apply the keybindings and placement from the user config
+    pub fn config(mut self, config: &Config) -> Self {
+        for &(combo, action) in &config.keys {
+            match action {
+                Some(action) => self.keymap.bind(combo, action),
+                None => self.keymap.unbind(combo),
+            }
+        }
+
+        if let Some(anchor) = config.anchor {
+            self = self.anchor(anchor);
+        }
+        if let Some(monitor) = &config.monitor {
+            self.monitor = monitor.clone();
+        }
+        let offset_x = config.offset_x.unwrap_or(self.placement.offset_x);
+        let offset_y = config.offset_y.unwrap_or(self.placement.offset_y);
+        self.placement = self
+            .placement
+            .offset(offset_x, offset_y)
+            .full_width(config.full_width.unwrap_or(self.placement.full_width))
+            .at_cursor(config.at_cursor.unwrap_or(self.placement.at_cursor));
+        self
+    }
+
+    /// Apply the colors and sizes from the user theme
+    pub fn theme_config(mut self, config: &ThemeConfig) -> Self {
+        if let Some(color) = config.background {
+            self.theme.background_color = color;
+        }
+        if let Some(color) = config.selected {
+            self.theme.shape_color = color;
+        }
+        if let Some(color) = config.text {
+            self.theme.label_color = color;
+        }
+        self.border_color = config.border.or(self.border_color);
+
+        // the row colors without a counterpart in the conrod theme are set as defaults for the
+        // row widget
+        let mut style = self
+            .theme
+            .widget_style::<row::Style>()
+            .map(|default| *default.style)
+            .unwrap_or_default();
+        style.highlight_color = config.highlight.or(style.highlight_color);
+        style.subtitle_color = config.subtitle.or(style.subtitle_color);
+        style.mark_color = config.mark.or(style.mark_color);
+        self.theme.widget_styling.insert(
+            TypeId::of::<row::Style>(),
+            WidgetDefault::new(Box::new(style)),
+        );
+
+        // the padding in the theme is the space left inside of the border
+        let padding = config.padding.unwrap_or(self.margin - self.border_width);
+        self.border_width = config.border_width.unwrap_or(self.border_width);
+        self.margin = self.border_width + padding;
+        self.row_height = config.row_height.or(self.row_height);
+        self.font_size = config.font_size.unwrap_or(self.font_size);
+        self.subtitle_font_size = config.subtitle_font_size.unwrap_or(self.subtitle_font_size);
+        if let Some(family) = &config.font {
+            self.font = Some(FontSource::family(family));
+        }
+        if let Some(families) = &config.fallback_fonts {
+            self.fallback_fonts = families
+                .iter()
+                .map(|family| FontSource::family(family))
+                .collect();
+        }
+        self
+    }
+
+    fn validate(&self) -> Result<(), BuildError> {
+        if self.width == 0 {
+            return Err(BuildError::InvalidWidth);
+        }
+        if self.font_size == 0 || self.subtitle_font_size == 0 {
+            return Err(BuildError::InvalidFontSize);
+        }
+        if self.max_rows == 0 {
+            return Err(BuildError::InvalidMaxRows);
+        }
+        if !self.placement.is_valid() {
+            return Err(BuildError::InvalidPlacement(self.placement));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn controller<Item: MenuItem>(&self) -> MenuController<Item> {
+        let state = AppState::new(
+            self.query.clone(),
+            self.multi_select,
+            self.wrap_around,
+            self.max_rows,
+        );
+        let mut controller = MenuController::new(state, self.keymap.clone());
+        controller.set_items(Vec::new());
+        controller
+    }
+
+    /// Validate the configuration and create a controller for the menu without any window,
+    /// for driving the menu logic directly
+    pub fn build_controller<Item: MenuItem>(self) -> Result<MenuController<Item>, BuildError> {
+        self.validate()?;
+        Ok(self.controller())
+    }
+
+    /// Validate the configuration and create the app
+    pub fn build<Item: MenuItem + Send + 'static>(mut self) -> Result<MenuApp<Item>, BuildError> {
+        self.validate()?;
+
+        // a font family also gives the other faces, font data only the regular one, which is
+        // then also used for italic text
+        let mut lookup = FontLookup::default();
+        let family = match &self.font {
+            Some(FontSource::Family(family)) => Some(FontSource::family(family)),
+            _ => None,
+        };
+        let bold = match self.bold_font.take().or_else(|| family.clone()) {
+            Some(font) => font.load(FontStyle::Bold, &mut lookup)?,
+            None => load_font(
+                include_bytes!("../assets/fonts/NotoSans/NotoSans-Bold.ttf").to_vec(),
+                0,
+            )?,
+        };
+        let (italic, bold_italic) = match (&self.font, &family) {
+            (_, Some(family)) => (
+                family
+                    .clone()
+                    .load_optional(FontStyle::Italic, &mut lookup)?,
+                family
+                    .clone()
+                    .load_optional(FontStyle::BoldItalic, &mut lookup)?,
+            ),
+            (None, None) => (
+                Some(load_font(
+                    include_bytes!("../assets/fonts/NotoSans/NotoSans-Italic.ttf").to_vec(),
+                    0,
+                )?),
+                Some(load_font(
+                    include_bytes!("../assets/fonts/NotoSans/NotoSans-BoldItalic.ttf").to_vec(),
+                    0,
+                )?),
+            ),
+            (Some(_), None) => (None, None),
+        };
+        let regular = match self.font.take() {
+            Some(font) => font.load(FontStyle::Regular, &mut lookup)?,
+            None => load_font(
+                include_bytes!("../assets/fonts/NotoSans/NotoSans-Regular.ttf").to_vec(),
+                0,
+            )?,
+        };
+        let faces = FontFaces {
+            regular,
+            bold,
+            italic,
+            bold_italic,
+        };
+        // the fallback fonts are loaded once the menu shows text the fonts above can't draw
+        let fallback = FallbackFonts::new(std::mem::take(&mut self.fallback_fonts), lookup);
+
+        let layout = Layout {
+            font_size: self.font_size,
+            subtitle_font_size: self.subtitle_font_size,
+            max_rows: self.max_rows,
+            fixed_height: self.fixed_height,
+            margin: self.margin,
+            border_width: self.border_width,
+            border_color: self.border_color,
+            row_height: self.row_height,
+        };
+
+        Ok(MenuApp::from_builder(self, faces, fallback, layout))
+    }
+}
@@ -0,0 +1,212 @@
+use crate::keys::{Action, KeyCombo, KeyError};
+use crate::monitor::MonitorSelection;
+use crate::placement::{Anchor, Offset, PlacementError};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fs::read_to_string;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Directory containing the config files, `$XDG_CONFIG_HOME/rufi`
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("rufi"))
+}
+
+/// Read the contents of a config file
+pub(crate) fn read_config_file(path: &Path) -> Result<String, ConfigError> {
+    read_to_string(path).map_err(|err| ConfigError::Io(path.into(), err))
+}
+
+/// The config file as written by the user
+#[derive(Debug, Default, Deserialize)]
+struct RawConfig {
+    #[serde(default)]
+    keys: BTreeMap<String, String>,
+    #[serde(default)]
+    placement: RawPlacement,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct RawPlacement {
+    anchor: Option<String>,
+    offset_x: Option<RawOffset>,
+    offset_y: Option<RawOffset>,
+    full_width: Option<bool>,
+    at_cursor: Option<bool>,
+    monitor: Option<RawMonitor>,
+}
+
+/// Offsets can be written as a number of pixels or as a string like `"5%"`
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawOffset {
+    Pixels(i32),
+    Text(String),
+}
+
+/// Monitors can be selected by index or with a string like `"cursor"` or `"DP-1"`
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawMonitor {
+    Index(i64),
+    Text(String),
+}
+
+impl RawOffset {
+    fn parse(&self) -> Result<Offset, PlacementError> {
+        match self {
+            RawOffset::Pixels(pixels) => Ok(Offset::Pixels(*pixels)),
+            RawOffset::Text(text) => text.parse(),
+        }
+    }
+}
+
+/// User configuration, read from `$XDG_CONFIG_HOME/rufi/config.toml`
+///
+/// ```toml
+/// [keys]
+/// "ctrl+n" = "down"
+/// "alt+1" = "custom-1"
+/// "Home" = "none"
+///
+/// [placement]
+/// anchor = "top"
+/// offset-y = "10%"
+/// full-width = true
+/// monitor = "focused"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    /// Keybindings to add to the default keymap, `None` removes the default binding
+    pub keys: Vec<(KeyCombo, Option<Action>)>,
+    /// Where on the monitor to place the window
+    pub anchor: Option<Anchor>,
+    pub offset_x: Option<Offset>,
+    pub offset_y: Option<Offset>,
+    /// Stretch the window over the full width of the monitor
+    pub full_width: Option<bool>,
+    /// Open the window at the mouse cursor
+    pub at_cursor: Option<bool>,
+    /// The monitor to open the window on
+    pub monitor: Option<MonitorSelection>,
+}
+
+impl Config {
+    /// Location of the config file
+    pub fn path() -> Option<PathBuf> {
+        Some(config_dir()?.join("config.toml"))
+    }
+
+    /// Load the config file of the user, a missing config file gives the default config
+    pub fn load() -> Result<Self, ConfigError> {
+        match Config::path() {
+            Some(path) if path.exists() => Config::from_file(&path),
+            _ => Ok(Config::default()),
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        read_config_file(path)?.parse()
+    }
+}
+
+impl std::str::FromStr for Config {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let raw: RawConfig = toml::from_str(s).map_err(ConfigError::Parse)?;
+
+        // different spellings can parse to the same combination, which of them would win is
+        // arbitrary so they are rejected
+        let mut bound = HashSet::new();
+        let keys = raw
+            .keys
+            .iter()
+            .map(|(name, action)| {
+                let combo: KeyCombo = name.parse()?;
+                if !bound.insert(combo) {
+                    return Err(KeyError::DuplicateKey(name.clone()));
+                }
+                let action = match action.as_str() {
+                    "none" => None,
+                    action => Some(action.parse()?),
+                };
+                Ok((combo, action))
+            })
+            .collect::<Result<_, KeyError>>()
+            .map_err(ConfigError::Key)?;
+
+        let placement = raw.placement;
+        let offset = |offset: Option<RawOffset>| offset.as_ref().map(RawOffset::parse).transpose();
+        Ok(Config {
+            keys,
+            anchor: placement
+                .anchor
+                .as_deref()
+                .map(str::parse)
+                .transpose()
+                .map_err(ConfigError::Placement)?,
+            offset_x: offset(placement.offset_x).map_err(ConfigError::Placement)?,
+            offset_y: offset(placement.offset_y).map_err(ConfigError::Placement)?,
+            full_width: placement.full_width,
+            at_cursor: placement.at_cursor,
+            monitor: placement
+                .monitor
+                .map(|monitor| match monitor {
+                    RawMonitor::Index(index) => MonitorSelection::from_index(index)
+                        .ok_or_else(|| PlacementError::InvalidMonitor(index.to_string())),
+                    RawMonitor::Text(text) => match text.parse() {
+                        Ok(monitor) => Ok(monitor),
+                        Err(never) => match never {},
+                    },
+                })
+                .transpose()
+                .map_err(ConfigError::Placement)?,
+        })
+    }
+}
+
+/// Error while loading the config file
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The config file couldn't be read
+    Io(PathBuf, io::Error),
+    /// The config file isn't valid toml
+    Parse(toml::de::Error),
+    /// Invalid key or action in the keybindings
+    Key(KeyError),
+    /// Invalid color in the theme, colors are written like `#rrggbb`, `#rgb` or `red`
+    Color(String),
+    /// Invalid anchor or offset for the window placement
+    Placement(PlacementError),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, err) => write!(f, "can't read {}: {}", path.display(), err),
+            ConfigError::Parse(err) => write!(f, "invalid config: {}", err),
+            ConfigError::Key(err) => write!(f, "invalid keybinding: {}", err),
+            ConfigError::Color(color) => write!(f, "invalid color: {}", color),
+            ConfigError::Placement(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConfigError::Io(_, err) => Some(err),
+            ConfigError::Parse(err) => Some(err),
+            ConfigError::Key(err) => Some(err),
+            ConfigError::Color(_) => None,
+            ConfigError::Placement(err) => Some(err),
+        }
+    }
+}
@@ -0,0 +1,496 @@
+use crate::item::MenuItem;
+use crate::keys::{Action, Keymap};
+use crate::matcher::{FuzzyMatcher, Match};
+use crate::search::{without_positions, SearchMessage, SearchResult};
+use std::borrow::Cow;
+use std::cmp::min;
+use std::collections::BTreeSet;
+use winit::event::{ModifiersState, VirtualKeyCode};
+
+/// The results, selection and query of the menu
+pub struct AppState<Item: MenuItem> {
+    pub(crate) items: Vec<Item>,
+    pub(crate) matches: Vec<Match>,
+    pub(crate) selected: usize,
+    pub(crate) search: String,
+    multi_select: bool,
+    /// Indices of the marked items when multi select is enabled
+    marked: BTreeSet<usize>,
+    /// The first row that is shown in the list
+    pub(crate) scroll: usize,
+    /// Number of rows that fit in the list, as last laid out by the gui
+    pub(crate) visible_rows: usize,
+    /// Move from the last row to the first and the other way around
+    wrap_around: bool,
+    /// Whether any of the results has a subtitle, updated when the results change so the gui
+    /// doesn't have to look at every result for every frame
+    pub(crate) any_subtitle: bool,
+    /// Whether any of the results has an icon
+    pub(crate) any_icon: bool,
+}
+
+impl<Item: MenuItem> AppState<Item> {
+    pub(crate) fn new(
+        search: String,
+        multi_select: bool,
+        wrap_around: bool,
+        visible_rows: usize,
+    ) -> Self {
+        AppState {
+            items: vec![],
+            matches: vec![],
+            selected: 0,
+            search,
+            multi_select,
+            marked: BTreeSet::new(),
+            scroll: 0,
+            visible_rows,
+            wrap_around,
+            any_subtitle: false,
+            any_icon: false,
+        }
+    }
+
+    pub fn set_search(&mut self, search: String) {
+        self.search = search
+    }
+
+    pub fn search(&self) -> &str {
+        &self.search
+    }
+
+    /// The matched items in the order they are shown
+    pub fn results(&self) -> impl Iterator<Item = &Item> {
+        self.matches
+            .iter()
+            .map(move |matched| &self.items[matched.index])
+    }
+
+    /// The row of the selected item in the results
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected_item(&self) -> Option<&Item> {
+        let matched = self.matches.get(self.selected)?;
+        Some(&self.items[matched.index])
+    }
+
+    /// The first row that is shown in the list
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    /// Filter the items against the current search using the built in fuzzy matcher
+    fn filter(&mut self) {
+        let matcher = FuzzyMatcher::new();
+        self.matches = matcher.filter(&self.items, &self.search);
+        for matched in &mut self.matches {
+            let item = &self.items[matched.index];
+            if item.search_key() != item.title() {
+                matched.positions = title_highlights(&matcher, item, &self.search);
+            }
+        }
+        self.selected = 0;
+        self.scroll = 0;
+
+        let (items, matches) = (&self.items, &self.matches);
+        let results = || matches.iter().map(|matched| &items[matched.index]);
+        let any_subtitle = results().any(|item| item.subtitle().is_some());
+        let any_icon = results().any(|item| item.icon().is_some());
+        self.any_subtitle = any_subtitle;
+        self.any_icon = any_icon;
+    }
+
+    /// Show the items returned from a search callback in the order they were returned
+    ///
+    /// Since the items are replaced, any marks are cleared
+    fn set_results(&mut self, items: Vec<SearchResult<Item>>) {
+        self.items.clear();
+        self.matches.clear();
+        self.marked.clear();
+        self.selected = 0;
+        self.scroll = 0;
+        self.any_subtitle = false;
+        self.any_icon = false;
+        self.append_results(items);
+    }
+
+    /// Add a batch of items returned from a search callback after the existing results
+    ///
+    /// When the search callback doesn't report which characters matched, the built in matcher
+    /// is used to find the characters to highlight
+    fn append_results(&mut self, items: Vec<SearchResult<Item>>) {
+        let matcher = FuzzyMatcher::new();
+        let offset = self.items.len();
+        for (index, (item, positions)) in items.into_iter().enumerate() {
+            let positions =
+                positions.unwrap_or_else(|| title_highlights(&matcher, &item, &self.search));
+            self.any_subtitle |= item.subtitle().is_some();
+            self.any_icon |= item.icon().is_some();
+            self.matches.push(Match {
+                index: offset + index,
+                score: 0,
+                positions,
+            });
+            self.items.push(item);
+        }
+    }
+
+    /// Move the selection up or down by a number of rows, stopping at the first and last row
+    ///
+    /// With wrap around enabled, moving past the first or last row while already on it
+    /// continues on the other end of the list.
+    fn move_selection(&mut self, rows: isize) {
+        let last = self.matches.len().saturating_sub(1);
+        self.selected = if self.wrap_around && rows < 0 && self.selected == 0 {
+            last
+        } else if self.wrap_around && rows > 0 && self.selected == last {
+            0
+        } else if rows < 0 {
+            self.selected.saturating_sub(-rows as usize)
+        } else {
+            min(self.selected + rows as usize, last)
+        };
+        self.scroll_to_selected();
+    }
+
+    /// Move the selection by the number of rows that fit in the list
+    fn move_page(&mut self, pages: isize) {
+        self.move_selection(pages * self.visible_rows.max(1) as isize);
+    }
+
+    fn select_first(&mut self) {
+        self.selected = 0;
+        self.scroll_to_selected();
+    }
+
+    fn select_last(&mut self) {
+        self.selected = self.matches.len().saturating_sub(1);
+        self.scroll_to_selected();
+    }
+
+    /// Scroll the list just enough to show the selected row
+    fn scroll_to_selected(&mut self) {
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.visible_rows > 0 && self.selected >= self.scroll + self.visible_rows {
+            self.scroll = self.selected + 1 - self.visible_rows;
+        }
+    }
+
+    /// Scroll the list by a number of rows without changing the selection
+    pub(crate) fn scroll_by(&mut self, rows: isize) {
+        let max_scroll = self.matches.len().saturating_sub(self.visible_rows);
+        self.scroll = if rows < 0 {
+            self.scroll.saturating_sub(-rows as usize)
+        } else {
+            min(self.scroll + rows as usize, max_scroll)
+        };
+    }
+
+    /// Complete the search to the longest prefix shared by the search keys of all results, or
+    /// to the search key of the selected item if that doesn't make the search any longer
+    ///
+    /// Returns true if the search changed
+    fn complete(&mut self) -> bool {
+        let items = &self.items;
+        let prefix = common_prefix(
+            self.matches
+                .iter()
+                .map(|matched| items[matched.index].search_key()),
+        );
+        let completion = match prefix {
+            Some(prefix) if prefix.len() > self.search.len() => prefix,
+            _ => match self.matches.get(self.selected) {
+                Some(matched) => items[matched.index].search_key().into_owned(),
+                None => return false,
+            },
+        };
+
+        if completion == self.search {
+            return false;
+        }
+        self.search = completion;
+        true
+    }
+
+    /// Remove the selected item, returning it with its index in the items
+    fn take_selected(&mut self) -> Option<(Item, usize)> {
+        let index = self.matches.get(self.selected)?.index;
+        self.matches.clear();
+        Some((self.items.remove(index), index))
+    }
+
+    pub fn is_marked(&self, row: usize) -> bool {
+        self.matches
+            .get(row)
+            .is_some_and(|matched| self.marked.contains(&matched.index))
+    }
+
+    fn set_marked(&mut self, row: usize, marked: bool) {
+        if let Some(matched) = self.matches.get(row) {
+            if marked {
+                self.marked.insert(matched.index);
+            } else {
+                self.marked.remove(&matched.index);
+            }
+        }
+    }
+
+    /// Toggle the mark on the selected row and move to the next row
+    fn toggle_mark(&mut self) {
+        if self.multi_select {
+            self.set_marked(self.selected, !self.is_marked(self.selected));
+            self.move_selection(1);
+        }
+    }
+
+    /// Remove all marked items, returning them in the order of the items with their index
+    fn take_marked(&mut self) -> Vec<(Item, usize)> {
+        let marked = std::mem::take(&mut self.marked);
+        let mut items: Vec<Option<Item>> = self.items.drain(..).map(Some).collect();
+        self.matches.clear();
+        marked
+            .into_iter()
+            .filter_map(|index| Some((items.get_mut(index)?.take()?, index)))
+            .collect()
+    }
+}
+
+/// Find the characters of the title to highlight for the search
+fn title_highlights<Item: MenuItem>(
+    matcher: &FuzzyMatcher,
+    item: &Item,
+    search: &str,
+) -> Vec<usize> {
+    matcher
+        .fuzzy_match(&item.title(), search)
+        .map(|(_, positions)| positions)
+        .unwrap_or_default()
+}
+
+/// The longest prefix shared by all keys
+fn common_prefix<'a>(keys: impl Iterator<Item = Cow<'a, str>>) -> Option<String> {
+    keys.fold(None, |prefix: Option<String>, key| match prefix {
+        None => Some(key.into_owned()),
+        Some(mut prefix) => {
+            let len = prefix
+                .char_indices()
+                .zip(key.chars())
+                .take_while(|((_, a), b)| a == b)
+                .last()
+                .map_or(0, |((index, c), _)| index + c.len_utf8());
+            prefix.truncate(len);
+            Some(prefix)
+        }
+    })
+}
+
+/// How the menu was closed
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome<Item> {
+    /// An item was selected, with the index of the item in the items
+    Selected(Item, usize),
+    /// Multiple items were marked in multi select mode, in the order of the items
+    Marked(Vec<(Item, usize)>),
+    /// The query was accepted as is, either because nothing matched or with `Action::AcceptCustom`
+    Custom(String),
+    /// The menu was closed with `Action::Cancel` or by closing the window
+    Cancelled,
+    /// The menu was closed because the window lost focus
+    FocusLost,
+    /// A key bound to `Action::Custom` was pressed, with the selected item
+    CustomKey(usize, Option<Item>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppEvent {
+    /// The query changed and the results for it need to be searched
+    Search(String),
+    Continue,
+    /// The menu is done, the outcome can be taken from the controller
+    Exit,
+}
+
+/// Input for the menu, independent of the windowing system
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    /// A key was pressed while holding the modifiers
+    Key(VirtualKeyCode, ModifiersState),
+    /// The query was edited
+    Query(String),
+    /// The menu lost focus
+    FocusLost,
+    /// The menu was closed by the windowing system
+    Close,
+}
+
+/// The selection, search and keyboard logic of the menu, without any window or renderer
+///
+/// This is what drives a `MenuApp`, but can also be used on its own to test the behavior of
+/// the menu.
+pub struct MenuController<Item: MenuItem> {
+    state: AppState<Item>,
+    keymap: Keymap,
+    /// Filter the items with the built in matcher when the query changes
+    filter_items: bool,
+    /// The first batch of results for a new query replaces the previous results
+    replace_results: bool,
+    outcome: Option<Outcome<Item>>,
+}
+
+impl<Item: MenuItem> MenuController<Item> {
+    pub(crate) fn new(state: AppState<Item>, keymap: Keymap) -> Self {
+        MenuController {
+            state,
+            keymap,
+            filter_items: true,
+            replace_results: false,
+            outcome: None,
+        }
+    }
+
+    pub fn state(&self) -> &AppState<Item> {
+        &self.state
+    }
+
+    pub(crate) fn state_mut(&mut self) -> &mut AppState<Item> {
+        &mut self.state
+    }
+
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        self.keymap = keymap;
+    }
+
+    /// Set the items to filter with the built in fuzzy matcher
+    pub fn set_items(&mut self, items: Vec<Item>) {
+        self.state.items = items;
+        self.state.filter();
+    }
+
+    /// Leave the search to a callback instead of filtering the items with the built in matcher
+    pub(crate) fn set_filter_items(&mut self, filter_items: bool) {
+        self.filter_items = filter_items;
+    }
+
+    /// Expect the results for a new query to be received in batches, the first of which
+    /// replaces the current results
+    pub(crate) fn expect_results(&mut self) {
+        self.replace_results = true;
+    }
+
+    /// Replace the results with the items returned from a search callback
+    pub fn set_results(&mut self, items: Vec<Item>) {
+        self.set_search_results(without_positions(items));
+    }
+
+    pub(crate) fn set_search_results(&mut self, results: Vec<SearchResult<Item>>) {
+        self.state.set_results(results);
+        self.replace_results = false;
+    }
+
+    /// Add a batch of items from a search callback, replacing the previous results if this is
+    /// the first batch for the query
+    pub fn append_results(&mut self, items: Vec<Item>) {
+        self.append_search_results(without_positions(items));
+    }
+
+    pub(crate) fn append_search_results(&mut self, results: Vec<SearchResult<Item>>) {
+        if self.replace_results {
+            self.set_search_results(results);
+        } else {
+            self.state.append_results(results);
+        }
+    }
+
+    /// The search for the query is done
+    pub fn finish_results(&mut self) {
+        if self.replace_results {
+            self.set_search_results(Vec::new());
+        }
+    }
+
+    pub(crate) fn handle_search_message(&mut self, message: SearchMessage<Item>) {
+        match message {
+            SearchMessage::Results { items, .. } => self.append_search_results(items),
+            SearchMessage::Finished { .. } => self.finish_results(),
+        }
+    }
+
+    /// Update the state for the input
+    ///
+    /// Returns `AppEvent::Search` if the results need to be searched for a new query and
+    /// `AppEvent::Exit` once the menu is done, any input after that is ignored so events that
+    /// were still queued don't replace the outcome.
+    pub fn handle_input(&mut self, input: Input) -> AppEvent {
+        if self.outcome.is_some() {
+            return AppEvent::Exit;
+        }
+        let state = &mut self.state;
+        let outcome = match input {
+            Input::Query(query) => return self.search(query),
+            Input::FocusLost => Outcome::FocusLost,
+            Input::Close => Outcome::Cancelled,
+            Input::Key(key, modifiers) => match self.keymap.action(key, modifiers) {
+                Some(Action::Accept) if !state.marked.is_empty() => {
+                    Outcome::Marked(state.take_marked())
+                }
+                Some(Action::Accept) => match state.take_selected() {
+                    Some((item, index)) => Outcome::Selected(item, index),
+                    None => Outcome::Custom(state.search.clone()),
+                },
+                Some(Action::AcceptCustom) => Outcome::Custom(state.search.clone()),
+                Some(Action::Cancel) => Outcome::Cancelled,
+                Some(Action::Custom(index)) => {
+                    let item = state.take_selected().map(|(item, _)| item);
+                    Outcome::CustomKey(index, item)
+                }
+                Some(Action::Complete) => {
+                    return match state.complete() {
+                        true => {
+                            let query = state.search.clone();
+                            self.search(query)
+                        }
+                        false => AppEvent::Continue,
+                    };
+                }
+                Some(action) => {
+                    match action {
+                        Action::Up => state.move_selection(-1),
+                        Action::Down => state.move_selection(1),
+                        Action::PageUp => state.move_page(-1),
+                        Action::PageDown => state.move_page(1),
+                        Action::First => state.select_first(),
+                        Action::Last => state.select_last(),
+                        Action::ToggleMark => state.toggle_mark(),
+                        _ => {}
+                    }
+                    return AppEvent::Continue;
+                }
+                None => return AppEvent::Continue,
+            },
+        };
+
+        self.outcome = Some(outcome);
+        AppEvent::Exit
+    }
+
+    fn search(&mut self, query: String) -> AppEvent {
+        self.state.set_search(query.clone());
+        if self.filter_items {
+            self.state.filter();
+        }
+        AppEvent::Search(query)
+    }
+
+    /// How the menu was closed, once `AppEvent::Exit` was returned
+    pub fn outcome(&self) -> Option<&Outcome<Item>> {
+        self.outcome.as_ref()
+    }
+
+    /// Take the outcome, if the menu was closed without one it counts as cancelled
+    pub fn into_outcome(self) -> Outcome<Item> {
+        self.outcome.unwrap_or(Outcome::Cancelled)
+    }
+}
@@ -0,0 +1,60 @@
+use winit::dpi::PhysicalPosition;
+use winit::window::Window;
+
+/// The position of the mouse cursor on the screen
+///
+/// winit only reports the cursor position inside our own window, so this asks the windowing
+/// system directly, which is only supported on X11.
+pub fn cursor_position(window: &Window) -> Option<PhysicalPosition<i32>> {
+    imp::cursor_position(window)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod imp {
+    use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+    use std::os::raw::{c_int, c_uint};
+    use winit::dpi::PhysicalPosition;
+    use winit::window::Window;
+    use x11_dl::xlib;
+
+    pub fn cursor_position(window: &Window) -> Option<PhysicalPosition<i32>> {
+        let handle = match window.raw_window_handle() {
+            RawWindowHandle::Xlib(handle) => handle,
+            _ => return None,
+        };
+        let xlib = xlib::Xlib::open().ok()?;
+        let display = handle.display as *mut xlib::Display;
+
+        let (mut root, mut child) = (0, 0);
+        let (mut root_x, mut root_y, mut window_x, mut window_y): (c_int, c_int, c_int, c_int) =
+            (0, 0, 0, 0);
+        let mut mask: c_uint = 0;
+        let on_screen = unsafe {
+            (xlib.XQueryPointer)(
+                display,
+                (xlib.XDefaultRootWindow)(display),
+                &mut root,
+                &mut child,
+                &mut root_x,
+                &mut root_y,
+                &mut window_x,
+                &mut window_y,
+                &mut mask,
+            )
+        };
+        match on_screen {
+            0 => None,
+            _ => Some(PhysicalPosition::new(root_x, root_y)),
+        }
+    }
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+mod imp {
+    use winit::dpi::PhysicalPosition;
+    use winit::window::Window;
+
+    pub fn cursor_position(_window: &Window) -> Option<PhysicalPosition<i32>> {
+        None
+    }
+}
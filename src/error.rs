@@ -0,0 +1,76 @@
+use crate::builder::BuildError;
+use crate::config::ConfigError;
+use conrod_core::text::rt::gpu_cache::CacheWriteErr;
+use std::fmt;
+use std::io;
+use winit::error::OsError;
+
+/// Error while setting up or showing the menu
+#[derive(Debug)]
+pub enum Error {
+    /// Invalid configuration passed to the `MenuAppBuilder`
+    Build(BuildError),
+    /// The config file couldn't be loaded
+    Config(ConfigError),
+    /// The window couldn't be created
+    Window(OsError),
+    /// No gpu is available and software rendering isn't supported by the windowing system
+    NoRenderer,
+    /// The text couldn't be added to the glyph cache of the gpu renderer
+    Render(CacheWriteErr),
+    /// Showing the menu on the Wayland layer shell failed
+    Wayland(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Build(err) => err.fmt(f),
+            Error::Config(err) => err.fmt(f),
+            Error::Window(err) => write!(f, "can't create window: {}", err),
+            Error::NoRenderer => write!(
+                f,
+                "no gpu available and software rendering is only supported on X11 and Windows"
+            ),
+            Error::Render(err) => write!(f, "can't render text: {}", err),
+            Error::Wayland(err) => write!(f, "wayland error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Build(err) => Some(err),
+            Error::Config(err) => Some(err),
+            Error::Window(err) => Some(err),
+            Error::NoRenderer => None,
+            Error::Render(err) => Some(err),
+            Error::Wayland(err) => Some(err),
+        }
+    }
+}
+
+impl From<BuildError> for Error {
+    fn from(err: BuildError) -> Self {
+        Error::Build(err)
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(err: ConfigError) -> Self {
+        Error::Config(err)
+    }
+}
+
+impl From<OsError> for Error {
+    fn from(err: OsError) -> Self {
+        Error::Window(err)
+    }
+}
+
+impl From<CacheWriteErr> for Error {
+    fn from(err: CacheWriteErr) -> Self {
+        Error::Render(err)
+    }
+}
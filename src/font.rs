@@ -0,0 +1,318 @@
+use crate::app::Fonts;
+use crate::builder::BuildError;
+use conrod_core::text::{font, Font, FontCollection, GlyphId};
+use std::collections::VecDeque;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Families tried for characters missing from the main font, when they are installed
+const DEFAULT_FALLBACK: &[&str] = &[
+    "Noto Sans CJK JP",
+    "Noto Sans Symbols",
+    "Noto Sans Symbols2",
+    "Noto Emoji",
+    "DejaVu Sans",
+];
+
+/// Where to load a font from
+#[derive(Debug, Clone, PartialEq)]
+pub enum FontSource {
+    /// Font data in ttf, otf or ttc format
+    Data(Vec<u8>),
+    /// A font family name, looked up with fontconfig or in the font directories
+    Family(String),
+}
+
+impl FontSource {
+    pub fn family(name: &str) -> Self {
+        FontSource::Family(name.to_string())
+    }
+
+    /// The default fallback chain, skipping the families that aren't installed
+    pub fn default_fallback() -> Vec<FontSource> {
+        DEFAULT_FALLBACK
+            .iter()
+            .map(|family| FontSource::family(family))
+            .collect()
+    }
+
+    /// Load the font, for a family the face closest to `style` is used
+    pub(crate) fn load(
+        self,
+        style: FontStyle,
+        lookup: &mut FontLookup,
+    ) -> Result<Font, BuildError> {
+        match self {
+            FontSource::Data(data) => load_font(data, 0),
+            FontSource::Family(family) => {
+                let file = lookup
+                    .find(&family, style)
+                    .ok_or(BuildError::FontNotFound(family))?;
+                let data = std::fs::read(&file.path)
+                    .map_err(|err| BuildError::FontFile(file.path.clone(), err))?;
+                load_font(data, file.index)
+            }
+        }
+    }
+
+    /// Like `load`, but a family that isn't installed gives `None`
+    pub(crate) fn load_optional(
+        self,
+        style: FontStyle,
+        lookup: &mut FontLookup,
+    ) -> Result<Option<Font>, BuildError> {
+        match self.load(style, lookup) {
+            Ok(font) => Ok(Some(font)),
+            Err(BuildError::FontNotFound(_)) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// The fonts loaded by the builder, to be added to the ui
+pub(crate) struct FontFaces {
+    pub regular: Font,
+    pub bold: Font,
+    /// Missing when only font data without italic faces is given
+    pub italic: Option<Font>,
+    pub bold_italic: Option<Font>,
+}
+
+/// Fallback fonts that are only looked up and loaded once a character is missing from the
+/// loaded fonts, as finding them takes a while and fonts like the CJK fonts are big
+pub(crate) struct FallbackFonts {
+    pending: VecDeque<FontSource>,
+    lookup: FontLookup,
+}
+
+impl FallbackFonts {
+    pub fn new(sources: Vec<FontSource>, lookup: FontLookup) -> Self {
+        FallbackFonts {
+            pending: sources.into(),
+            lookup,
+        }
+    }
+
+    /// Load fallback fonts in order until every character of the text can be drawn, or until
+    /// there are no fallback fonts left
+    pub fn cover(&mut self, text: &str, fonts: &mut Fonts, map: &mut font::Map) {
+        for c in text.chars() {
+            if self.pending.is_empty() {
+                return;
+            }
+            while !c.is_whitespace() && !c.is_control() && !has_glyph(fonts, map, c) {
+                let source = match self.pending.pop_front() {
+                    Some(source) => source,
+                    None => return,
+                };
+                // missing or broken fallback fonts are skipped, they're only a best effort
+                if let Ok(Some(font)) = source.load_optional(FontStyle::Regular, &mut self.lookup) {
+                    fonts.fallback.push(map.insert(font));
+                }
+            }
+        }
+    }
+}
+
+/// Whether the regular font or one of the loaded fallback fonts has a glyph for the character
+fn has_glyph(fonts: &Fonts, map: &font::Map, c: char) -> bool {
+    std::iter::once(fonts.regular)
+        .chain(fonts.fallback.iter().cloned())
+        .filter_map(|font_id| map.get(font_id))
+        .any(|font| font.glyph(c).id() != GlyphId(0))
+}
+
+/// The face of a font family
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontStyle {
+    Regular,
+    Bold,
+    Italic,
+    BoldItalic,
+}
+
+impl FontStyle {
+    /// Style as used in fontconfig patterns
+    fn pattern(self) -> &'static str {
+        match self {
+            FontStyle::Regular => "regular",
+            FontStyle::Bold => "bold",
+            FontStyle::Italic => "italic",
+            FontStyle::BoldItalic => "bold:italic",
+        }
+    }
+
+    /// Style as used in font file names, like `NotoSans-BoldItalic.ttf`
+    fn file_name(self) -> &'static str {
+        match self {
+            FontStyle::Regular => "regular",
+            FontStyle::Bold => "bold",
+            FontStyle::Italic => "italic",
+            FontStyle::BoldItalic => "bolditalic",
+        }
+    }
+}
+
+/// A font file and the index of the font inside the file, for font collections
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontFile {
+    pub path: PathBuf,
+    pub index: u32,
+}
+
+pub(crate) fn load_font(data: Vec<u8>, index: u32) -> Result<Font, BuildError> {
+    FontCollection::from_bytes(data)
+        .and_then(|collection| collection.font_at(index as usize))
+        .map_err(BuildError::InvalidFont)
+}
+
+/// Find the font file for a family, with fontconfig or by scanning the font directories when
+/// fontconfig isn't available
+pub fn find_font(family: &str, style: FontStyle) -> Option<FontFile> {
+    FontLookup::default().find(family, style)
+}
+
+/// Finds font files, remembering what it found out about the system for the next lookups
+#[derive(Debug, Default)]
+pub(crate) struct FontLookup {
+    /// `fc-match` couldn't be run, so it isn't tried again
+    no_fontconfig: bool,
+    /// The files in the font directories, scanned once for all lookups without fontconfig
+    files: Option<Vec<PathBuf>>,
+}
+
+impl FontLookup {
+    pub fn find(&mut self, family: &str, style: FontStyle) -> Option<FontFile> {
+        if !self.no_fontconfig {
+            match fontconfig_match(family, style) {
+                Ok(file) => return file,
+                Err(_) => self.no_fontconfig = true,
+            }
+        }
+        let files = self.files.get_or_insert_with(scan_font_dirs);
+        find_font_file(files, family, style)
+    }
+}
+
+/// Ask `fc-match` for the font, only accepting the result if it's of the requested family
+/// as fontconfig always returns its closest match
+fn fontconfig_match(family: &str, style: FontStyle) -> std::io::Result<Option<FontFile>> {
+    let escaped: String = family
+        .chars()
+        .flat_map(|c| match c {
+            '\\' | '-' | ':' | ',' => vec!['\\', c],
+            c => vec![c],
+        })
+        .collect();
+    let output = Command::new("fc-match")
+        .arg("--format=%{file}\n%{index}\n%{family}")
+        .arg(format!("{}:{}", escaped, style.pattern()))
+        .output()?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let output = String::from_utf8_lossy(&output.stdout);
+    let mut lines = output.lines();
+    let (path, index, families) = match (lines.next(), lines.next(), lines.next()) {
+        (Some(path), Some(index), Some(families)) => (path, index, families),
+        _ => return Ok(None),
+    };
+    let found = families
+        .split(',')
+        .any(|name| name.trim().eq_ignore_ascii_case(family.trim()));
+    if !found {
+        return Ok(None);
+    }
+
+    Ok(Some(FontFile {
+        path: PathBuf::from(path),
+        index: index.parse().unwrap_or(0),
+    }))
+}
+
+/// The directories containing fonts, as used by fontconfig by default
+fn font_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    match env::var_os("XDG_DATA_HOME") {
+        Some(data_home) => dirs.push(Path::new(&data_home).join("fonts")),
+        None => {
+            if let Some(home) = env::var_os("HOME") {
+                dirs.push(Path::new(&home).join(".local/share/fonts"));
+            }
+        }
+    }
+    if let Some(home) = env::var_os("HOME") {
+        dirs.push(Path::new(&home).join(".fonts"));
+    }
+    let data_dirs =
+        env::var_os("XDG_DATA_DIRS").unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+    dirs.extend(env::split_paths(&data_dirs).map(|dir| dir.join("fonts")));
+    dirs
+}
+
+/// All font files in the font directories
+fn scan_font_dirs() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = font_dirs();
+
+    while let Some(dir) = pending.pop() {
+        let entries = match dir.read_dir() {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+
+            let is_font = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| {
+                    ["ttf", "otf", "ttc"].contains(&extension.to_ascii_lowercase().as_str())
+                });
+            if is_font {
+                files.push(path);
+            }
+        }
+    }
+    files
+}
+
+/// Look for a font file named after the family and style, like `NotoSans-Bold.ttf`
+///
+/// Font files without a style in the name are taken as the regular face.
+fn find_font_file(files: &[PathBuf], family: &str, style: FontStyle) -> Option<FontFile> {
+    let family = normalize(family);
+    files.iter().find_map(|path| {
+        let stem = path.file_stem()?.to_str()?;
+        let (name, face) = match stem.find('-') {
+            Some(split) => (&stem[..split], normalize(&stem[split + 1..])),
+            None => (stem, String::new()),
+        };
+        let face_matches = match style {
+            FontStyle::Regular => face.is_empty() || face == style.file_name(),
+            _ => face == style.file_name(),
+        };
+        if normalize(name) == family && face_matches {
+            Some(FontFile {
+                path: path.clone(),
+                index: 0,
+            })
+        } else {
+            None
+        }
+    })
+}
+
+/// Lowercase the name and drop anything but letters and digits, so `Noto Sans` matches the
+/// file name `NotoSans`
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
@@ -0,0 +1,348 @@
+use crate::item::Icon;
+use conrod_core::image::Id;
+use image::RgbaImage;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::ffi::OsStr;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use winit::event_loop::EventLoopProxy;
+
+const FALLBACK_THEME: &str = "hicolor";
+
+/// The icon file types that can be loaded
+fn extensions() -> &'static [&'static str] {
+    if cfg!(feature = "svg") {
+        &["png", "svg"]
+    } else {
+        &["png"]
+    }
+}
+
+/// Key to identify an icon in the cache, image data is identified by the allocation
+#[derive(Debug, Clone)]
+enum IconKey {
+    Path(PathBuf),
+    Name(String),
+    Image(Arc<RgbaImage>),
+}
+
+impl From<&Icon> for IconKey {
+    fn from(icon: &Icon) -> Self {
+        match icon {
+            Icon::Path(path) => IconKey::Path(path.clone()),
+            Icon::Name(name) => IconKey::Name(name.clone()),
+            Icon::Image(image) => IconKey::Image(image.clone()),
+        }
+    }
+}
+
+impl PartialEq for IconKey {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (IconKey::Path(a), IconKey::Path(b)) => a == b,
+            (IconKey::Name(a), IconKey::Name(b)) => a == b,
+            (IconKey::Image(a), IconKey::Image(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for IconKey {}
+
+impl Hash for IconKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            IconKey::Path(path) => path.hash(state),
+            IconKey::Name(name) => name.hash(state),
+            IconKey::Image(image) => (&**image as *const RgbaImage as usize).hash(state),
+        }
+    }
+}
+
+/// Cache of the icons uploaded into the image map
+///
+/// Icons are requested while building the gui and loaded on a background thread, so only the
+/// icons for the rows that are actually shown are loaded, every icon is only loaded once and
+/// the menu doesn't wait for the disk.
+pub struct IconCache {
+    loaded: HashMap<IconKey, Option<Id>>,
+    /// Icons send to the loader thread that haven't been received back yet
+    requested: HashSet<IconKey>,
+    request_tx: Sender<IconKey>,
+    loaded_rx: Receiver<(IconKey, Option<RgbaImage>)>,
+}
+
+impl IconCache {
+    /// Start the loader thread, which wakes up the event loop every time an icon is loaded
+    pub fn new(theme: &str, size: u32, proxy: Option<EventLoopProxy<()>>) -> Self {
+        let (request_tx, request_rx) = channel::<IconKey>();
+        let (loaded_tx, loaded_rx) = channel();
+        let theme = theme.to_string();
+
+        std::thread::spawn(move || {
+            for key in request_rx {
+                let image = load_icon(&key, &theme, size);
+                if loaded_tx.send((key, image)).is_err() {
+                    break;
+                }
+                if let Some(proxy) = &proxy {
+                    let _ = proxy.send_event(());
+                }
+            }
+        });
+
+        IconCache {
+            loaded: HashMap::new(),
+            requested: HashSet::new(),
+            request_tx,
+            loaded_rx,
+        }
+    }
+
+    /// Get the image for an icon, if the icon isn't loaded yet it's requested from the loader
+    /// thread and available after a later call to `upload_loaded`
+    pub fn get(&mut self, icon: &Icon) -> Option<Id> {
+        let key = IconKey::from(icon);
+        if let Some(id) = self.loaded.get(&key) {
+            return *id;
+        }
+        if !self.requested.contains(&key) {
+            match self.request_tx.send(key.clone()) {
+                Ok(()) => {
+                    self.requested.insert(key);
+                }
+                Err(_) => {
+                    self.loaded.insert(key, None);
+                }
+            }
+        }
+        None
+    }
+
+    /// Whether requested icons are still being loaded
+    pub fn is_loading(&self) -> bool {
+        !self.requested.is_empty()
+    }
+
+    /// Hand the icons loaded so far to `upload` to be added to the image map of the renderer,
+    /// returns true if any new icon is available
+    pub fn upload_loaded<Upload>(&mut self, mut upload: Upload) -> bool
+    where
+        Upload: FnMut(RgbaImage) -> Id,
+    {
+        let mut loaded_any = false;
+        while let Ok((key, image)) = self.loaded_rx.try_recv() {
+            loaded_any |= self.insert(key, image, &mut upload);
+        }
+        loaded_any
+    }
+
+    /// Like `upload_loaded`, but first wait until all requested icons are loaded
+    pub fn wait_loaded<Upload>(&mut self, mut upload: Upload) -> bool
+    where
+        Upload: FnMut(RgbaImage) -> Id,
+    {
+        let mut loaded_any = false;
+        while self.is_loading() {
+            match self.loaded_rx.recv() {
+                Ok((key, image)) => loaded_any |= self.insert(key, image, &mut upload),
+                Err(_) => break,
+            }
+        }
+        loaded_any
+    }
+
+    fn insert<Upload>(&mut self, key: IconKey, image: Option<RgbaImage>, upload: Upload) -> bool
+    where
+        Upload: FnMut(RgbaImage) -> Id,
+    {
+        self.requested.remove(&key);
+        let id = image.map(upload);
+        self.loaded.insert(key, id);
+        id.is_some()
+    }
+}
+
+fn load_icon(key: &IconKey, theme: &str, size: u32) -> Option<RgbaImage> {
+    match key {
+        IconKey::Path(path) => load_image(path, size),
+        IconKey::Name(name) => {
+            find_icon(name, theme, size).and_then(|path| load_image(&path, size))
+        }
+        IconKey::Image(image) => Some(fit(RgbaImage::clone(image), size)),
+    }
+}
+
+/// Scale the image to fit a square of `size` pixels keeping its aspect ratio, non square
+/// images are centered on a transparent background
+fn fit(image: RgbaImage, size: u32) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    if width == size && height == size {
+        return image;
+    }
+
+    let scale = size as f64 / width.max(height) as f64;
+    let scaled_width = ((width as f64 * scale).round() as u32).max(1);
+    let scaled_height = ((height as f64 * scale).round() as u32).max(1);
+    let image = match (scaled_width, scaled_height) == (width, height) {
+        true => image,
+        false => image::imageops::resize(
+            &image,
+            scaled_width,
+            scaled_height,
+            image::FilterType::Triangle,
+        ),
+    };
+    if scaled_width == size && scaled_height == size {
+        return image;
+    }
+
+    let mut square = RgbaImage::new(size, size);
+    image::imageops::replace(
+        &mut square,
+        &image,
+        (size - scaled_width) / 2,
+        (size - scaled_height) / 2,
+    );
+    square
+}
+
+fn load_image(path: &Path, size: u32) -> Option<RgbaImage> {
+    let image = if path.extension() == Some(OsStr::new("svg")) {
+        load_svg(path, size)?
+    } else {
+        image::open(path).ok()?.to_rgba()
+    };
+    Some(fit(image, size))
+}
+
+#[cfg(feature = "svg")]
+fn load_svg(path: &Path, size: u32) -> Option<RgbaImage> {
+    let data = std::fs::read(path).ok()?;
+    let tree = usvg::Tree::from_data(&data, &usvg::Options::default().to_ref()).ok()?;
+    // scale the longest side to the requested size, keeping the aspect ratio
+    let svg_size = tree.svg_node().size;
+    let fit_to = match svg_size.width() >= svg_size.height() {
+        true => usvg::FitTo::Width(size),
+        false => usvg::FitTo::Height(size),
+    };
+    let pixmap_size = fit_to.fit_to(svg_size.to_screen_size())?;
+    let mut pixmap = tiny_skia::Pixmap::new(pixmap_size.width(), pixmap_size.height())?;
+    resvg::render(
+        &tree,
+        fit_to,
+        tiny_skia::Transform::default(),
+        pixmap.as_mut(),
+    )?;
+    // the pixmap is premultiplied, the images aren't
+    let data = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            vec![color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    RgbaImage::from_raw(pixmap.width(), pixmap.height(), data)
+}
+
+#[cfg(not(feature = "svg"))]
+fn load_svg(_path: &Path, _size: u32) -> Option<RgbaImage> {
+    None
+}
+
+/// The base directories to look for icon themes as specified by the freedesktop icon theme spec
+fn icon_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(home) = env::var_os("HOME") {
+        dirs.push(Path::new(&home).join(".icons"));
+    }
+    match env::var_os("XDG_DATA_HOME") {
+        Some(data_home) => dirs.push(Path::new(&data_home).join("icons")),
+        None => {
+            if let Some(home) = env::var_os("HOME") {
+                dirs.push(Path::new(&home).join(".local/share/icons"));
+            }
+        }
+    }
+    let data_dirs =
+        env::var_os("XDG_DATA_DIRS").unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+    dirs.extend(env::split_paths(&data_dirs).map(|dir| dir.join("icons")));
+    dirs
+}
+
+/// Find an icon by name in the icon theme, falling back to the hicolor theme and pixmaps
+///
+/// This doesn't parse the `index.theme` files but picks the size directory closest to the
+/// requested size, preferring scalable icons when no exact size is available.
+pub fn find_icon(name: &str, theme: &str, size: u32) -> Option<PathBuf> {
+    let path = Path::new(name);
+    if path.is_absolute() {
+        return Some(path.to_path_buf()).filter(|path| path.exists());
+    }
+
+    let dirs = icon_dirs();
+    let themes = if theme == FALLBACK_THEME {
+        vec![theme]
+    } else {
+        vec![theme, FALLBACK_THEME]
+    };
+
+    for theme in themes {
+        for dir in &dirs {
+            if let Some(found) = find_in_theme(&dir.join(theme), name, size) {
+                return Some(found);
+            }
+        }
+    }
+
+    extensions()
+        .iter()
+        .map(|extension| Path::new("/usr/share/pixmaps").join(format!("{}.{}", name, extension)))
+        .find(|path| path.exists())
+}
+
+fn find_in_theme(theme_dir: &Path, name: &str, size: u32) -> Option<PathBuf> {
+    let mut size_dirs: Vec<(u32, PathBuf)> = theme_dir
+        .read_dir()
+        .ok()?
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let dir_name = entry.file_name();
+            let dir_name = dir_name.to_str()?;
+            let distance = if dir_name == "scalable" {
+                1
+            } else {
+                let dir_size: u32 = dir_name.split('x').next()?.parse().ok()?;
+                match dir_size {
+                    dir_size if dir_size == size => 0,
+                    // prefer scaling down over scaling up
+                    dir_size if dir_size > size => 2 + dir_size - size,
+                    dir_size => 2 + 2 * (size - dir_size),
+                }
+            };
+            Some((distance, entry.path()))
+        })
+        .collect();
+    size_dirs.sort();
+
+    for (_, size_dir) in size_dirs {
+        let categories = match size_dir.read_dir() {
+            Ok(categories) => categories,
+            Err(_) => continue,
+        };
+        for category in categories.filter_map(Result::ok) {
+            for extension in extensions() {
+                let path = category.path().join(format!("{}.{}", name, extension));
+                if path.exists() {
+                    return Some(path);
+                }
+            }
+        }
+    }
+
+    None
+}
@@ -0,0 +1,52 @@
+use image::RgbaImage;
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// An icon shown next to an item
+#[derive(Debug, Clone)]
+pub enum Icon {
+    /// Path to an image file
+    Path(PathBuf),
+    /// Name of an icon in the freedesktop icon theme
+    Name(String),
+    /// Already decoded image data
+    Image(Arc<RgbaImage>),
+}
+
+/// An item that can be shown in the menu
+///
+/// Any type implementing `Display` is a menu item that only has a title.
+pub trait MenuItem {
+    /// The main text for the item
+    fn title(&self) -> Cow<'_, str>;
+
+    /// The title with Pango like markup for bold, italic and colored text, like
+    /// `<b>bold</b>`, `<i>italic</i>` and `<span foreground="#ff0000">red</span>`
+    ///
+    /// The text without the tags should be the same as the title, which is used for matching.
+    fn markup(&self) -> Option<Cow<'_, str>> {
+        None
+    }
+
+    /// A second line of text shown below the title
+    fn subtitle(&self) -> Option<Cow<'_, str>> {
+        None
+    }
+
+    fn icon(&self) -> Option<&Icon> {
+        None
+    }
+
+    /// The text the search query is matched against, defaults to the title
+    fn search_key(&self) -> Cow<'_, str> {
+        self.title()
+    }
+}
+
+impl<T: Display> MenuItem for T {
+    fn title(&self) -> Cow<'_, str> {
+        Cow::Owned(self.to_string())
+    }
+}
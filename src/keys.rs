@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use winit::event::{ModifiersState, VirtualKeyCode};
+
+/// An action that can be bound to a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// Accept the selected item, or the marked items in multi select mode
+    Accept,
+    /// Accept the query as typed
+    AcceptCustom,
+    Cancel,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+    /// Complete the query from the results
+    Complete,
+    /// Toggle the mark on the selected item in multi select mode
+    ToggleMark,
+    /// Close the menu with `Outcome::CustomKey`
+    Custom(usize),
+}
+
+impl FromStr for Action {
+    type Err = KeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "accept" => Action::Accept,
+            "accept-custom" => Action::AcceptCustom,
+            "cancel" => Action::Cancel,
+            "up" => Action::Up,
+            "down" => Action::Down,
+            "page-up" => Action::PageUp,
+            "page-down" => Action::PageDown,
+            "first" => Action::First,
+            "last" => Action::Last,
+            "complete" => Action::Complete,
+            "toggle-mark" => Action::ToggleMark,
+            _ => match s
+                .strip_prefix("custom-")
+                .and_then(|index| index.parse().ok())
+            {
+                Some(index) => Action::Custom(index),
+                None => return Err(KeyError::InvalidAction(s.to_string())),
+            },
+        })
+    }
+}
+
+/// A key together with the modifiers that need to be held
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyCombo {
+    pub key: VirtualKeyCode,
+    pub modifiers: ModifiersState,
+}
+
+impl KeyCombo {
+    pub fn new(key: VirtualKeyCode, modifiers: ModifiersState) -> Self {
+        KeyCombo { key, modifiers }
+    }
+}
+
+impl From<VirtualKeyCode> for KeyCombo {
+    fn from(key: VirtualKeyCode) -> Self {
+        KeyCombo::new(key, ModifiersState::empty())
+    }
+}
+
+impl FromStr for KeyCombo {
+    type Err = KeyError;
+
+    /// Parse a key combination like `ctrl+shift+n` or `Page_Down`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        let key = parts.pop().unwrap_or_default();
+        let key = parse_key(key).ok_or_else(|| KeyError::InvalidKey(s.to_string()))?;
+
+        let mut modifiers = ModifiersState::empty();
+        for modifier in parts {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "shift" => ModifiersState::SHIFT,
+                "ctrl" | "control" => ModifiersState::CTRL,
+                "alt" | "mod1" => ModifiersState::ALT,
+                "super" | "logo" | "mod4" => ModifiersState::LOGO,
+                _ => return Err(KeyError::InvalidKey(s.to_string())),
+            };
+        }
+
+        Ok(KeyCombo::new(key, modifiers))
+    }
+}
+
+/// Invalid key or action name in a keybinding
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyError {
+    InvalidKey(String),
+    InvalidAction(String),
+    /// Two spellings of the same key combination, like `ctrl+n` and `control+N`
+    DuplicateKey(String),
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyError::InvalidKey(key) => write!(f, "invalid key: {}", key),
+            KeyError::InvalidAction(action) => write!(f, "invalid action: {}", action),
+            KeyError::DuplicateKey(key) => write!(f, "key bound more than once: {}", key),
+        }
+    }
+}
+
+impl std::error::Error for KeyError {}
+
+const LETTERS: [VirtualKeyCode; 26] = {
+    use VirtualKeyCode::*;
+    [
+        A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    ]
+};
+const DIGITS: [VirtualKeyCode; 10] = {
+    use VirtualKeyCode::*;
+    [Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9]
+};
+const FUNCTION_KEYS: [VirtualKeyCode; 24] = {
+    use VirtualKeyCode::*;
+    [
+        F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12, F13, F14, F15, F16, F17, F18, F19, F20,
+        F21, F22, F23, F24,
+    ]
+};
+
+/// The other keys by their xkb keysym and names, the names are the keysym names followed by
+/// aliases, matched without case and underscores
+const KEYS: &[(u32, &[&str], VirtualKeyCode)] = {
+    use VirtualKeyCode::*;
+    &[
+        (0xff0d, &["return", "enter"], Return),
+        (0xff1b, &["escape", "esc"], Escape),
+        (0xff09, &["tab"], Tab),
+        (0xfe20, &["isolefttab"], Tab),
+        (0x0020, &["space"], Space),
+        (0xff08, &["backspace"], Back),
+        (0xffff, &["delete", "del"], Delete),
+        (0xff63, &["insert", "ins"], Insert),
+        (0xff52, &["up"], Up),
+        (0xff54, &["down"], Down),
+        (0xff51, &["left"], Left),
+        (0xff53, &["right"], Right),
+        (0xff55, &["pageup", "prior"], PageUp),
+        (0xff56, &["pagedown", "next"], PageDown),
+        (0xff50, &["home"], Home),
+        (0xff57, &["end"], End),
+        (0x0027, &["apostrophe"], Apostrophe),
+        (0x002c, &["comma"], Comma),
+        (0x002d, &["minus"], Minus),
+        (0x002e, &["period"], Period),
+        (0x002f, &["slash"], Slash),
+        (0x003a, &["colon"], Colon),
+        (0x003b, &["semicolon"], Semicolon),
+        (0x003d, &["equal", "equals"], Equals),
+        (0x0040, &["at"], At),
+        (0x005b, &["bracketleft"], LBracket),
+        (0x005c, &["backslash"], Backslash),
+        (0x005d, &["bracketright"], RBracket),
+        (0x0060, &["grave"], Grave),
+        (0xff8d, &["kpenter"], Return),
+        (0xffb0, &["kp0"], Numpad0),
+        (0xffb1, &["kp1"], Numpad1),
+        (0xffb2, &["kp2"], Numpad2),
+        (0xffb3, &["kp3"], Numpad3),
+        (0xffb4, &["kp4"], Numpad4),
+        (0xffb5, &["kp5"], Numpad5),
+        (0xffb6, &["kp6"], Numpad6),
+        (0xffb7, &["kp7"], Numpad7),
+        (0xffb8, &["kp8"], Numpad8),
+        (0xffb9, &["kp9"], Numpad9),
+        (0xffab, &["kpadd"], Add),
+        (0xffad, &["kpsubtract"], Subtract),
+        (0xffaa, &["kpmultiply"], Multiply),
+        (0xffaf, &["kpdivide"], Divide),
+        (0xffae, &["kpdecimal"], Decimal),
+        (0xff97, &["kpup"], Up),
+        (0xff99, &["kpdown"], Down),
+        (0xff96, &["kpleft"], Left),
+        (0xff98, &["kpright"], Right),
+        (0xff9a, &["kppageup", "kpprior"], PageUp),
+        (0xff9b, &["kppagedown", "kpnext"], PageDown),
+        (0xff95, &["kphome"], Home),
+        (0xff9c, &["kpend"], End),
+        (0xff9e, &["kpinsert"], Insert),
+        (0xff9f, &["kpdelete"], Delete),
+    ]
+};
+
+/// The key for a name like `n`, `F5`, `Page_Down` or `KP_Enter`
+fn parse_key(key: &str) -> Option<VirtualKeyCode> {
+    let name = key.to_lowercase().replace('_', "");
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return match c {
+            'a'..='z' => Some(LETTERS[(c as u8 - b'a') as usize]),
+            '0'..='9' => Some(DIGITS[(c as u8 - b'0') as usize]),
+            _ => None,
+        };
+    }
+    if let Some(number) = name
+        .strip_prefix('f')
+        .and_then(|number| number.parse::<usize>().ok())
+    {
+        return FUNCTION_KEYS.get(number.wrapping_sub(1)).copied();
+    }
+
+    KEYS.iter()
+        .find(|(_, names, _)| names.contains(&name.as_str()))
+        .map(|&(_, _, key)| key)
+}
+
+/// The key for an xkb keysym, as send by Wayland compositors
+#[cfg(all(feature = "layer-shell", unix, not(target_os = "macos")))]
+pub(crate) fn key_from_keysym(keysym: u32) -> Option<VirtualKeyCode> {
+    match keysym {
+        0x0061..=0x007a => Some(LETTERS[(keysym - 0x0061) as usize]),
+        0x0041..=0x005a => Some(LETTERS[(keysym - 0x0041) as usize]),
+        0x0030..=0x0039 => Some(DIGITS[(keysym - 0x0030) as usize]),
+        0xffbe..=0xffd5 => Some(FUNCTION_KEYS[(keysym - 0xffbe) as usize]),
+        _ => KEYS
+            .iter()
+            .find(|&&(sym, _, _)| sym == keysym)
+            .map(|&(_, _, key)| key),
+    }
+}
+
+/// Mapping from key combinations to actions
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    bindings: HashMap<KeyCombo, Action>,
+}
+
+impl Keymap {
+    /// A keymap without any bindings
+    pub fn empty() -> Self {
+        Keymap {
+            bindings: HashMap::new(),
+        }
+    }
+
+    pub fn bind(&mut self, combo: KeyCombo, action: Action) {
+        self.bindings.insert(combo, action);
+    }
+
+    pub fn unbind(&mut self, combo: KeyCombo) {
+        self.bindings.remove(&combo);
+    }
+
+    /// The action bound to the key with the currently held modifiers
+    pub fn action(&self, key: VirtualKeyCode, modifiers: ModifiersState) -> Option<Action> {
+        self.bindings.get(&KeyCombo::new(key, modifiers)).copied()
+    }
+}
+
+impl Default for Keymap {
+    /// The arrow keys, emacs style ctrl+n/ctrl+p and vi style ctrl+j/ctrl+k for moving and
+    /// alt+1 to alt+9 for the custom actions
+    fn default() -> Self {
+        use VirtualKeyCode::*;
+
+        let none = ModifiersState::empty();
+        let shift = ModifiersState::SHIFT;
+        let ctrl = ModifiersState::CTRL;
+        let alt = ModifiersState::ALT;
+
+        let mut keymap = Keymap::empty();
+        let bindings = [
+            (Return, none, Action::Accept),
+            (Return, shift, Action::AcceptCustom),
+            (Escape, none, Action::Cancel),
+            (G, ctrl, Action::Cancel),
+            (Up, none, Action::Up),
+            (P, ctrl, Action::Up),
+            (K, ctrl, Action::Up),
+            (Down, none, Action::Down),
+            (N, ctrl, Action::Down),
+            (J, ctrl, Action::Down),
+            (PageUp, none, Action::PageUp),
+            (B, ctrl, Action::PageUp),
+            (PageDown, none, Action::PageDown),
+            (F, ctrl, Action::PageDown),
+            (Home, none, Action::First),
+            (End, none, Action::Last),
+            (Tab, none, Action::Complete),
+            (Tab, shift, Action::ToggleMark),
+            (Space, ctrl, Action::ToggleMark),
+        ];
+        for &(key, modifiers, action) in bindings.iter() {
+            keymap.bind(KeyCombo::new(key, modifiers), action);
+        }
+
+        let digits = [Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9];
+        for (index, &key) in digits.iter().enumerate() {
+            keymap.bind(KeyCombo::new(key, alt), Action::Custom(index + 1));
+        }
+
+        keymap
+    }
+}
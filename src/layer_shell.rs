@@ -0,0 +1,540 @@
+use crate::app::{MenuApp, ICON_SIZE};
+use crate::controller::{AppEvent, Input, Outcome};
+use crate::error::Error;
+use crate::icon::IconCache;
+use crate::item::MenuItem;
+use crate::keys::key_from_keysym;
+use crate::monitor::MonitorSelection;
+use crate::placement::Placement;
+use crate::raster::Rasterizer;
+use crate::search::SearchBackend;
+use crate::window::convert_key;
+use conrod_core::event::Input as UiInput;
+use conrod_core::input::Button;
+use conrod_core::Ui;
+use image::{Pixel, RgbaImage};
+use smithay_client_toolkit::keyboard::{
+    map_keyboard_auto_with_repeat, Event as KbEvent, KeyRepeatEvent, KeyRepeatKind, KeyState,
+    ModifiersState as KbModifiers,
+};
+use smithay_client_toolkit::reexports::client::protocol::{
+    wl_output::{Transform, WlOutput},
+    wl_seat::WlSeat,
+    wl_shm,
+    wl_surface::WlSurface,
+};
+use smithay_client_toolkit::reexports::client::{Display, EventQueue};
+use smithay_client_toolkit::utils::DoubleMemPool;
+use smithay_client_toolkit::Environment;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::sync::mpsc::channel;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use wayland_protocols::unstable::xdg_output::v1::client::zxdg_output_manager_v1::ZxdgOutputManagerV1;
+use wayland_protocols::unstable::xdg_output::v1::client::zxdg_output_v1;
+use wayland_protocols::wlr::unstable::layer_shell::v1::client::zwlr_layer_shell_v1::{
+    Layer, ZwlrLayerShellV1,
+};
+use wayland_protocols::wlr::unstable::layer_shell::v1::client::zwlr_layer_surface_v1::{
+    self, Anchor, ZwlrLayerSurfaceV1,
+};
+use winit::event::{ModifiersState, VirtualKeyCode};
+
+/// How often to check for results and icons while they're loaded in the background
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A connection to a Wayland compositor that supports the wlr layer shell protocol
+///
+/// The menu is shown as a surface on the overlay layer, which the compositor places at the
+/// anchors we ask for and which gets all keyboard input while it's open. Compared to the winit
+/// window there is no pointer input and the menu is drawn at the integer scale of the output.
+pub(crate) struct LayerShell {
+    display: Display,
+    event_queue: EventQueue,
+    env: Environment,
+    layer_shell: ZwlrLayerShellV1,
+    seat: WlSeat,
+    /// For the connector names of the outputs, not supported by every compositor
+    xdg_output_manager: Option<ZxdgOutputManagerV1>,
+}
+
+/// Events from the compositor, send from the wayland callbacks to the menu loop
+enum WaylandEvent {
+    Key {
+        key: Option<VirtualKeyCode>,
+        pressed: bool,
+        text: Option<String>,
+    },
+    /// The state of the modifier keys changed
+    Modifiers(ModifiersState),
+    /// A held key is repeated
+    Repeat {
+        key: Option<VirtualKeyCode>,
+        text: Option<String>,
+    },
+    /// The compositor decided on the width of the surface, 0 leaves the width to us. The
+    /// height always follows the items
+    Configure(u32),
+    /// A buffer is released and can be drawn to again
+    BufferReleased,
+    FocusLost,
+    Closed,
+}
+
+impl LayerShell {
+    /// Connect to the compositor, `None` when not running on Wayland or when the compositor
+    /// doesn't support the layer shell
+    pub fn connect() -> Option<Self> {
+        let (display, mut event_queue) = Display::connect_to_env().ok()?;
+        let env = Environment::from_display(&display, &mut event_queue).ok()?;
+        let layer_shell = env
+            .manager
+            .instantiate_exact::<ZwlrLayerShellV1, _>(1, |layer_shell| {
+                layer_shell.implement_dummy()
+            })
+            .ok()?;
+        let seat = env
+            .manager
+            .instantiate_range::<WlSeat, _>(1, 5, |seat| seat.implement_dummy())
+            .ok()?;
+        // the names are only send since version 2
+        let xdg_output_manager = env
+            .manager
+            .instantiate_range::<ZxdgOutputManagerV1, _>(2, 3, |manager| manager.implement_dummy())
+            .ok();
+
+        Some(LayerShell {
+            display,
+            event_queue,
+            env,
+            layer_shell,
+            seat,
+            xdg_output_manager,
+        })
+    }
+
+    /// Show the menu on the overlay layer until it's done
+    pub fn run<Item>(
+        self,
+        mut app: MenuApp<Item>,
+        mut backend: SearchBackend<Item>,
+    ) -> Result<Outcome<Item>, Error>
+    where
+        Item: MenuItem + Send + 'static,
+    {
+        let LayerShell {
+            display,
+            mut event_queue,
+            env,
+            layer_shell,
+            seat,
+            xdg_output_manager,
+        } = self;
+        let (tx, rx) = channel();
+
+        let (output, output_size, scale) = find_output(
+            &env,
+            xdg_output_manager.as_ref(),
+            &mut event_queue,
+            &app.monitor_selection,
+        );
+        let surface = env
+            .compositor
+            .create_surface(|surface| surface.implement_dummy())
+            .map_err(|_| protocol_error("can't create surface"))?;
+        // the surface is sized in logical pixels, the buffer is drawn at the scale of the output
+        surface.set_buffer_scale(scale);
+        let layer_surface = {
+            let tx = tx.clone();
+            layer_shell
+                .get_layer_surface(
+                    &surface,
+                    output.as_ref(),
+                    Layer::Overlay,
+                    "rufi".to_string(),
+                    move |layer_surface| {
+                        layer_surface.implement_closure(
+                            move |event, layer_surface| match event {
+                                zwlr_layer_surface_v1::Event::Configure {
+                                    serial, width, ..
+                                } => {
+                                    layer_surface.ack_configure(serial);
+                                    let _ = tx.send(WaylandEvent::Configure(width));
+                                }
+                                zwlr_layer_surface_v1::Event::Closed => {
+                                    let _ = tx.send(WaylandEvent::Closed);
+                                }
+                                _ => {}
+                            },
+                            (),
+                        )
+                    },
+                )
+                .map_err(|_| protocol_error("can't create layer surface"))?
+        };
+        layer_surface.set_keyboard_interactivity(1);
+        let size = (app.ui.win_w as u32, app.ui.win_h as u32);
+        place(&layer_surface, &app.placement, size, output_size);
+        // the first commit without a buffer asks the compositor to configure the surface
+        surface.commit();
+
+        let _keyboard = {
+            let tx = tx.clone();
+            let repeat_tx = tx.clone();
+            map_keyboard_auto_with_repeat(
+                &seat,
+                KeyRepeatKind::System,
+                move |event, _| match event {
+                    KbEvent::Key {
+                        keysym,
+                        state,
+                        utf8,
+                        ..
+                    } => {
+                        let _ = tx.send(WaylandEvent::Key {
+                            key: key_from_keysym(keysym),
+                            pressed: state == KeyState::Pressed,
+                            text: utf8,
+                        });
+                    }
+                    KbEvent::Modifiers { modifiers } => {
+                        let _ = tx.send(WaylandEvent::Modifiers(convert_modifiers(modifiers)));
+                    }
+                    KbEvent::Leave { .. } => {
+                        let _ = tx.send(WaylandEvent::FocusLost);
+                    }
+                    _ => {}
+                },
+                move |event: KeyRepeatEvent, _| {
+                    let _ = repeat_tx.send(WaylandEvent::Repeat {
+                        key: key_from_keysym(event.keysym),
+                        text: event.utf8,
+                    });
+                },
+            )
+            .map_err(|_| protocol_error("can't load the keymap, is xkbcommon installed?"))?
+        };
+
+        let mut pool = {
+            let tx = tx.clone();
+            DoubleMemPool::new(&env.shm, move || {
+                let _ = tx.send(WaylandEvent::BufferReleased);
+            })
+            .map_err(Error::Wayland)?
+        };
+
+        let mut icons = IconCache::new(&app.icon_theme, ICON_SIZE, None);
+        let mut image_map = conrod_core::image::Map::new();
+
+        app.start(&mut backend);
+
+        let mut modifiers = ModifiersState::empty();
+        let mut configured = false;
+        let mut state_updated = true;
+        // false while the compositor still uses both buffers
+        let mut buffer_free = true;
+        'menu: loop {
+            // only block on the compositor when there is nothing else to wait for, which
+            // includes waiting for a buffer to be released
+            let draw = configured && state_updated && buffer_free;
+            let loading = backend.is_searching() || icons.is_loading();
+            if configured && !draw && !loading {
+                event_queue.dispatch().map_err(Error::Wayland)?;
+            } else {
+                if loading && !draw {
+                    thread::sleep(POLL_INTERVAL);
+                }
+                event_queue.sync_roundtrip().map_err(Error::Wayland)?;
+            }
+
+            let mut inputs = Vec::new();
+            for event in rx.try_iter() {
+                match event {
+                    WaylandEvent::Key { key, pressed, text } => {
+                        handle_key(&mut app.ui, &mut inputs, key, pressed, text, modifiers);
+                    }
+                    WaylandEvent::Modifiers(state) => modifiers = state,
+                    WaylandEvent::Repeat { key, text } => {
+                        handle_key(&mut app.ui, &mut inputs, key, true, text, modifiers);
+                    }
+                    WaylandEvent::Configure(width) => {
+                        // anchored to both sides for full width, the width is up to the
+                        // compositor
+                        if width != 0 && width as f64 != app.ui.win_w {
+                            let height = app.ui.win_h;
+                            app.ui.handle_event(UiInput::Resize(width as f64, height));
+                        }
+                        configured = true;
+                    }
+                    WaylandEvent::BufferReleased => buffer_free = true,
+                    WaylandEvent::FocusLost => inputs.push(Input::FocusLost),
+                    WaylandEvent::Closed => inputs.push(Input::Close),
+                }
+                state_updated = true;
+            }
+
+            if app.receive_results(&mut backend) {
+                state_updated = true;
+            }
+            if icons.upload_loaded(|icon| image_map.insert(icon)) {
+                state_updated = true;
+            }
+
+            if configured && state_updated && buffer_free {
+                state_updated = false;
+                let update = app.update_gui(&mut icons);
+                inputs.extend(update.query.map(Input::Query));
+
+                if update.height as f64 != app.ui.win_h {
+                    let width = app.ui.win_w;
+                    app.ui
+                        .handle_event(UiInput::Resize(width, update.height as f64));
+                    state_updated = true;
+                }
+
+                let size = (app.ui.win_w as u32, app.ui.win_h as u32);
+                place(&layer_surface, &app.placement, size, output_size);
+                let mut rasterizer = Rasterizer::new(app.ui.win_w, app.ui.win_h, scale as f64);
+                rasterizer.draw(app.ui.draw(), &image_map);
+                // when both buffers are still in use we draw again once one is released
+                if !present(&mut pool, &surface, rasterizer.image(), scale)
+                    .map_err(Error::Wayland)?
+                {
+                    buffer_free = false;
+                    state_updated = true;
+                }
+            }
+
+            for input in inputs {
+                if let AppEvent::Exit = app.handle_input(&mut backend, input) {
+                    break 'menu;
+                }
+                state_updated = true;
+            }
+        }
+
+        layer_surface.destroy();
+        surface.destroy();
+        display.flush().map_err(Error::Wayland)?;
+
+        Ok(app.controller.into_outcome())
+    }
+}
+
+fn protocol_error(message: &str) -> Error {
+    Error::Wayland(io::Error::new(io::ErrorKind::Other, message))
+}
+
+/// An output with the info needed to find and place the menu on it
+struct Output {
+    output: WlOutput,
+    /// Make and model, for compositors that don't tell the connector names
+    model: String,
+    /// The connector name like `DP-1`
+    name: Option<String>,
+    /// The size in logical pixels, which the layer surface is sized and placed in
+    size: Option<(u32, u32)>,
+    scale: i32,
+}
+
+/// The output for the monitor selection, its logical size and its scale
+///
+/// Outputs are selected by index or by their connector name like `DP-1`, or by their make and
+/// model when the compositor doesn't tell the names. Without a matching output the compositor
+/// picks one, usually the focused output, in which case the size and scale of the first output
+/// are used for placing and drawing the menu.
+fn find_output(
+    env: &Environment,
+    xdg_output_manager: Option<&ZxdgOutputManagerV1>,
+    event_queue: &mut EventQueue,
+    selection: &MonitorSelection,
+) -> (Option<WlOutput>, Option<(u32, u32)>, i32) {
+    let mut outputs: Vec<Output> = env.outputs.with_all(|outputs| {
+        outputs
+            .iter()
+            .map(|(_, output, info)| {
+                let scale = info.scale_factor.max(1);
+                // without xdg-output the logical size is the mode divided by the scale, turned
+                // for rotated outputs
+                let size = info.modes.iter().find(|mode| mode.is_current).map(|mode| {
+                    let width = (mode.dimensions.0 / scale) as u32;
+                    let height = (mode.dimensions.1 / scale) as u32;
+                    match info.transform {
+                        Transform::_90
+                        | Transform::_270
+                        | Transform::Flipped90
+                        | Transform::Flipped270 => (height, width),
+                        _ => (width, height),
+                    }
+                });
+                Output {
+                    output: output.clone(),
+                    model: format!("{} {}", info.make, info.model),
+                    name: None,
+                    size,
+                    scale,
+                }
+            })
+            .collect()
+    });
+    if let Some(manager) = xdg_output_manager {
+        add_xdg_output_info(manager, &mut outputs, event_queue);
+    }
+
+    let found = match selection {
+        MonitorSelection::Index(index) => outputs.get(*index),
+        MonitorSelection::Name(name) => outputs
+            .iter()
+            .find(|output| output.name.as_ref() == Some(name) || &output.model == name),
+        // the cursor and focused window aren't known to Wayland clients, the compositor picks
+        // the focused output itself
+        _ => None,
+    };
+    let info = found.or_else(|| outputs.first());
+    (
+        found.map(|output| output.output.clone()),
+        info.and_then(|output| output.size),
+        info.map_or(1, |output| output.scale),
+    )
+}
+
+/// Add the connector names and logical sizes of the outputs, as told by xdg-output
+fn add_xdg_output_info(
+    manager: &ZxdgOutputManagerV1,
+    outputs: &mut [Output],
+    event_queue: &mut EventQueue,
+) {
+    type XdgInfo = Arc<Mutex<(Option<String>, Option<(u32, u32)>)>>;
+
+    let infos: Vec<XdgInfo> = outputs.iter().map(|_| XdgInfo::default()).collect();
+    let xdg_outputs: Vec<_> = outputs
+        .iter()
+        .zip(&infos)
+        .filter_map(|(output, info)| {
+            let info = info.clone();
+            manager
+                .get_xdg_output(&output.output, move |xdg_output| {
+                    xdg_output.implement_closure(
+                        move |event, _| match event {
+                            zxdg_output_v1::Event::Name { name } => {
+                                info.lock().unwrap().0 = Some(name);
+                            }
+                            zxdg_output_v1::Event::LogicalSize { width, height } => {
+                                info.lock().unwrap().1 = Some((width as u32, height as u32));
+                            }
+                            _ => {}
+                        },
+                        (),
+                    )
+                })
+                .ok()
+        })
+        .collect();
+
+    let _ = event_queue.sync_roundtrip();
+    for xdg_output in xdg_outputs {
+        xdg_output.destroy();
+    }
+    for (output, info) in outputs.iter_mut().zip(infos) {
+        let (name, size) = info.lock().unwrap().clone();
+        output.name = name;
+        output.size = size.or(output.size);
+    }
+}
+
+/// Anchor the surface for the placement, the compositor can't be asked for a position
+fn place(
+    layer_surface: &ZwlrLayerSurfaceV1,
+    placement: &Placement,
+    size: (u32, u32),
+    output_size: Option<(u32, u32)>,
+) {
+    let anchors = placement.edge_anchors(size, output_size);
+    let mut anchor = Anchor::empty();
+    anchor.set(Anchor::Top, anchors.top);
+    anchor.set(Anchor::Right, anchors.right);
+    anchor.set(Anchor::Bottom, anchors.bottom);
+    anchor.set(Anchor::Left, anchors.left);
+
+    let [top, right, bottom, left] = anchors.margin;
+    layer_surface.set_size(anchors.width, anchors.height);
+    layer_surface.set_anchor(anchor);
+    layer_surface.set_margin(top, right, bottom, left);
+}
+
+/// Copy the image into a shared memory buffer and show it on the surface
+///
+/// The image is drawn at the buffer scale of the surface. Returns `false` if both buffers are
+/// still used by the compositor.
+fn present(
+    pool: &mut DoubleMemPool,
+    surface: &WlSurface,
+    image: &RgbaImage,
+    scale: i32,
+) -> io::Result<bool> {
+    let pool = match pool.pool() {
+        Some(pool) => pool,
+        None => return Ok(false),
+    };
+    let (width, height) = image.dimensions();
+    let stride = width * 4;
+    pool.resize((stride * height) as usize)?;
+    pool.seek(SeekFrom::Start(0))?;
+    {
+        // argb8888 is stored in little endian, so as bgra bytes
+        let mut writer = BufWriter::new(&mut *pool);
+        for pixel in image.pixels() {
+            let (r, g, b, a) = pixel.channels4();
+            writer.write_all(&[b, g, r, a])?;
+        }
+        writer.flush()?;
+    }
+
+    let buffer = pool.buffer(
+        0,
+        width as i32,
+        height as i32,
+        stride as i32,
+        wl_shm::Format::Argb8888,
+    );
+    surface.attach(Some(&buffer), 0, 0);
+    surface.damage(0, 0, width as i32 / scale, height as i32 / scale);
+    surface.commit();
+    Ok(true)
+}
+
+/// Pass a key event to the ui, and the key presses to the controller
+fn handle_key(
+    ui: &mut Ui,
+    inputs: &mut Vec<Input>,
+    key: Option<VirtualKeyCode>,
+    pressed: bool,
+    text: Option<String>,
+    modifiers: ModifiersState,
+) {
+    if let Some(key) = key {
+        let button = Button::Keyboard(convert_key(key));
+        ui.handle_event(match pressed {
+            true => UiInput::Press(button),
+            false => UiInput::Release(button),
+        });
+        if pressed {
+            inputs.push(Input::Key(key, modifiers));
+        }
+    }
+    // like winit, no text is typed while ctrl or alt is held
+    let typing = pressed && !modifiers.ctrl() && !modifiers.alt();
+    if let Some(text) = text.filter(|text| typing && !text.chars().any(char::is_control)) {
+        ui.handle_event(UiInput::Text(text));
+    }
+}
+
+fn convert_modifiers(modifiers: KbModifiers) -> ModifiersState {
+    let mut state = ModifiersState::empty();
+    state.set(ModifiersState::SHIFT, modifiers.shift);
+    state.set(ModifiersState::CTRL, modifiers.ctrl);
+    state.set(ModifiersState::ALT, modifiers.alt);
+    state.set(ModifiersState::LOGO, modifiers.logo);
+    state
+}
@@ -1,9 +1,43 @@
 mod app;
+mod builder;
+mod config;
+mod controller;
+mod cursor;
+mod error;
+mod font;
+mod icon;
+mod item;
+mod keys;
+#[cfg(all(feature = "layer-shell", unix, not(target_os = "macos")))]
+mod layer_shell;
+mod markup;
+mod matcher;
+mod monitor;
+mod placement;
+mod raster;
 // mod renderer;
 mod renderer;
+mod row;
+mod search;
+mod software;
 mod support;
+mod theme;
 mod window;
 
-pub use app::{AppEvent, AppState, MenuApp};
+pub use app::MenuApp;
+pub use builder::{BuildError, MenuAppBuilder};
+pub use config::{Config, ConfigError};
+pub use controller::{AppEvent, AppState, Input, MenuController, Outcome};
+pub use error::Error;
+pub use font::{find_font, FontFile, FontSource, FontStyle};
+pub use item::{Icon, MenuItem};
+pub use keys::{Action, KeyCombo, KeyError, Keymap};
+pub use markup::{parse_markup, strip_markup, MarkupItem, Span};
+pub use matcher::{FuzzyMatcher, Match};
+pub use monitor::MonitorSelection;
+pub use placement::{Anchor, EdgeAnchors, Offset, Placement, PlacementError};
+pub use raster::Rasterizer;
+pub use search::{CancellationToken, ResultSink, SearchMode};
+pub use theme::ThemeConfig;
 // pub use renderer::Renderer;
 // pub use rendy::init::winit::event_loop::EventsLoop;
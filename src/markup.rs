@@ -0,0 +1,208 @@
+use crate::item::MenuItem;
+use crate::theme::parse_color;
+use conrod_core::Color;
+use std::borrow::Cow;
+
+/// A run of text with the same style
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+    /// Color of the text, `None` uses the color of the theme
+    pub color: Option<Color>,
+}
+
+impl Span {
+    /// A span without any styling
+    pub fn plain(text: &str) -> Self {
+        Span {
+            text: text.to_string(),
+            bold: false,
+            italic: false,
+            color: None,
+        }
+    }
+}
+
+/// The style set by an open tag
+#[derive(Debug, Clone, Copy, Default)]
+struct Style {
+    bold: bool,
+    italic: bool,
+    color: Option<Color>,
+}
+
+/// Parse text with a Pango like markup subset into styled spans
+///
+/// Supported are `<b>`, `<i>` and `<span>` with the `foreground` (or `fgcolor`, `color`),
+/// `weight` and `style` attributes, and the `&amp;`, `&lt;`, `&gt;`, `&quot;`, `&apos;` and
+/// numeric entities. The tags `<u>`, `<s>`, `<small>`, `<big>`, `<tt>`, `<sub>` and `<sup>`
+/// are accepted but their content is shown unstyled.
+///
+/// Returns `None` for invalid markup, like unknown tags or unbalanced tags, like rofi these
+/// items should be shown as the raw text.
+pub fn parse_markup(markup: &str) -> Option<Vec<Span>> {
+    let mut spans: Vec<Span> = Vec::new();
+    // the open tags with the style in effect inside of them
+    let mut stack: Vec<(&str, Style)> = Vec::new();
+    let mut rest = markup;
+
+    while !rest.is_empty() {
+        let style = stack.last().map(|&(_, style)| style).unwrap_or_default();
+
+        if let Some(tag) = rest.strip_prefix('<') {
+            let end = tag.find('>')?;
+            let (tag, after) = (&tag[..end], &tag[end + 1..]);
+            rest = after;
+
+            if let Some(name) = tag.strip_prefix('/') {
+                match stack.pop() {
+                    Some((open, _)) if open == name.trim() => continue,
+                    _ => return None,
+                }
+            }
+            let (name, attributes) = match tag.find(char::is_whitespace) {
+                Some(split) => (&tag[..split], &tag[split..]),
+                None => (tag, ""),
+            };
+            let style = match name {
+                "b" => Style {
+                    bold: true,
+                    ..style
+                },
+                "i" => Style {
+                    italic: true,
+                    ..style
+                },
+                "span" => span_style(attributes, style)?,
+                "u" | "s" | "small" | "big" | "tt" | "sub" | "sup" => style,
+                _ => return None,
+            };
+            stack.push((name, style));
+            continue;
+        }
+
+        let end = rest.find('<').unwrap_or(rest.len());
+        let text = unescape(&rest[..end])?;
+        rest = &rest[end..];
+
+        match spans.last_mut() {
+            Some(last)
+                if last.bold == style.bold
+                    && last.italic == style.italic
+                    && last.color == style.color =>
+            {
+                last.text.push_str(&text)
+            }
+            _ => spans.push(Span {
+                text,
+                bold: style.bold,
+                italic: style.italic,
+                color: style.color,
+            }),
+        }
+    }
+
+    match stack.is_empty() {
+        true => Some(spans),
+        false => None,
+    }
+}
+
+/// Apply the attributes of a `<span>` tag to the style of the surrounding text
+fn span_style(mut attributes: &str, mut style: Style) -> Option<Style> {
+    loop {
+        attributes = attributes.trim_start();
+        if attributes.is_empty() {
+            return Some(style);
+        }
+
+        let equals = attributes.find('=')?;
+        let name = attributes[..equals].trim();
+        let value = attributes[equals + 1..].trim_start();
+        let quote = value.chars().next().filter(|&c| c == '"' || c == '\'')?;
+        let end = value[1..].find(quote)? + 1;
+        let value_text = &value[1..end];
+        attributes = &value[end + 1..];
+
+        match name {
+            "foreground" | "fgcolor" | "color" => style.color = Some(parse_color(value_text)?),
+            "weight" => style.bold = matches!(value_text, "bold" | "heavy" | "ultrabold"),
+            "style" => style.italic = matches!(value_text, "italic" | "oblique"),
+            // other attributes like the font size aren't supported, but don't make the markup
+            // invalid
+            _ => {}
+        }
+    }
+}
+
+/// Replace the entities in the text by the characters they stand for
+fn unescape(text: &str) -> Option<String> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        let end = rest[start..].find(';')? + start;
+        let entity = &rest[start + 1..end];
+        let c = match entity {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = match entity.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => entity.strip_prefix('#')?.parse().ok()?,
+                };
+                std::char::from_u32(code)?
+            }
+        };
+        result.push(c);
+        rest = &rest[end + 1..];
+    }
+
+    result.push_str(rest);
+    Some(result)
+}
+
+/// The text of the markup without the tags, or the raw markup if it's invalid
+pub fn strip_markup(markup: &str) -> String {
+    match parse_markup(markup) {
+        Some(spans) => spans.iter().map(|span| span.text.as_str()).collect(),
+        None => markup.to_string(),
+    }
+}
+
+/// A menu item with a title written in markup, the fuzzy matcher only sees the text
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkupItem {
+    markup: String,
+    text: String,
+}
+
+impl MarkupItem {
+    pub fn new(markup: &str) -> Self {
+        MarkupItem {
+            markup: markup.to_string(),
+            text: strip_markup(markup),
+        }
+    }
+
+    /// The title with the markup, as it was passed to `new`
+    pub fn into_markup(self) -> String {
+        self.markup
+    }
+}
+
+impl MenuItem for MarkupItem {
+    fn title(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.text)
+    }
+
+    fn markup(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(&self.markup))
+    }
+}
@@ -0,0 +1,244 @@
+use crate::item::MenuItem;
+use std::cmp::Ordering;
+
+const SCORE_MATCH: i64 = 16;
+const SCORE_GAP_START: i64 = -3;
+const SCORE_GAP_EXTENSION: i64 = -1;
+const BONUS_CONSECUTIVE: i64 = 8;
+const BONUS_BOUNDARY: i64 = 10;
+const BONUS_CAMEL_CASE: i64 = 8;
+const BONUS_FIRST_CHAR: i64 = 6;
+
+/// An item that matched the search query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    /// Index of the item in the list that was matched against
+    pub index: usize,
+    /// How well the item matched the query, higher is better
+    pub score: i64,
+    /// Character (not byte) offsets of the matched characters in the item text
+    pub positions: Vec<usize>,
+}
+
+/// Fuzzy matcher in the style of fzf or skim
+///
+/// All characters of the pattern have to occur in the text in order, matches are then scored
+/// higher when the matched characters are consecutive or start a word.
+///
+/// Matching is case insensitive unless the pattern contains an uppercase character.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FuzzyMatcher;
+
+impl FuzzyMatcher {
+    pub fn new() -> Self {
+        FuzzyMatcher
+    }
+
+    /// Match a single text against the pattern, returning the score and matched positions
+    pub fn fuzzy_match(&self, text: &str, pattern: &str) -> Option<(i64, Vec<usize>)> {
+        if pattern.is_empty() {
+            return Some((0, Vec::new()));
+        }
+
+        let case_sensitive = pattern.chars().any(char::is_uppercase);
+        let text: Vec<char> = text.chars().collect();
+        let pattern: Vec<char> = pattern.chars().collect();
+        let eq = |a: char, b: char| {
+            if case_sensitive {
+                a == b
+            } else {
+                a == b || a.to_lowercase().eq(b.to_lowercase())
+            }
+        };
+
+        // find the first position where the full pattern is matched
+        let mut pattern_index = 0;
+        let mut end = None;
+        for (i, c) in text.iter().enumerate() {
+            if eq(*c, pattern[pattern_index]) {
+                pattern_index += 1;
+                if pattern_index == pattern.len() {
+                    end = Some(i);
+                    break;
+                }
+            }
+        }
+        let end = end?;
+
+        // then walk back to find the shortest window ending there
+        let mut pattern_index = pattern.len() - 1;
+        let mut start = 0;
+        for i in (0..=end).rev() {
+            if eq(text[i], pattern[pattern_index]) {
+                if pattern_index == 0 {
+                    start = i;
+                    break;
+                }
+                pattern_index -= 1;
+            }
+        }
+
+        let mut positions = Vec::with_capacity(pattern.len());
+        let mut pattern_index = 0;
+        for (i, c) in text.iter().enumerate().take(end + 1).skip(start) {
+            if pattern_index < pattern.len() && eq(*c, pattern[pattern_index]) {
+                positions.push(i);
+                pattern_index += 1;
+            }
+        }
+
+        Some((score(&text, &positions), positions))
+    }
+
+    /// Match the search key of all items against the pattern, returning the matches ordered
+    /// from best to worst
+    pub fn filter<Item: MenuItem>(&self, items: &[Item], pattern: &str) -> Vec<Match> {
+        let mut matches: Vec<(Match, usize)> = items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                let text = item.search_key();
+                self.fuzzy_match(&text, pattern).map(|(score, positions)| {
+                    (
+                        Match {
+                            index,
+                            score,
+                            positions,
+                        },
+                        text.chars().count(),
+                    )
+                })
+            })
+            .collect();
+
+        if !pattern.is_empty() {
+            matches.sort_by(|(a, a_len), (b, b_len)| compare(a, *a_len, b, *b_len));
+        }
+
+        matches.into_iter().map(|(m, _)| m).collect()
+    }
+}
+
+fn compare(a: &Match, a_len: usize, b: &Match, b_len: usize) -> Ordering {
+    b.score
+        .cmp(&a.score)
+        .then(a_len.cmp(&b_len))
+        .then(a.index.cmp(&b.index))
+}
+
+fn score(text: &[char], positions: &[usize]) -> i64 {
+    let mut score = 0;
+    let mut previous: Option<usize> = None;
+
+    for &position in positions {
+        score += SCORE_MATCH;
+
+        match previous {
+            Some(previous) if previous + 1 == position => score += BONUS_CONSECUTIVE,
+            Some(previous) => {
+                let gap = (position - previous - 1) as i64;
+                score += SCORE_GAP_START + SCORE_GAP_EXTENSION * (gap - 1);
+            }
+            None if position == 0 => score += BONUS_FIRST_CHAR,
+            None => {}
+        }
+
+        if position == 0 || !text[position - 1].is_alphanumeric() {
+            score += BONUS_BOUNDARY;
+        } else if text[position - 1].is_lowercase() && text[position].is_uppercase() {
+            score += BONUS_CAMEL_CASE;
+        }
+
+        previous = Some(position);
+    }
+
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(text: &str, pattern: &str) -> Option<Vec<usize>> {
+        FuzzyMatcher::new()
+            .fuzzy_match(text, pattern)
+            .map(|(_, positions)| positions)
+    }
+
+    fn score(text: &str, pattern: &str) -> i64 {
+        FuzzyMatcher::new().fuzzy_match(text, pattern).unwrap().0
+    }
+
+    #[test]
+    fn test_positions() {
+        assert_eq!(positions("hello world", "hw"), Some(vec![0, 6]));
+        assert_eq!(positions("hello world", "lo"), Some(vec![3, 4]));
+    }
+
+    #[test]
+    fn test_positions_are_characters() {
+        assert_eq!(positions("héllo", "lo"), Some(vec![3, 4]));
+    }
+
+    #[test]
+    fn test_shortest_window() {
+        // the first full match ends at the last `b`, from which the closest `a` is used
+        assert_eq!(positions("axxab", "ab"), Some(vec![3, 4]));
+    }
+
+    #[test]
+    fn test_empty_pattern() {
+        assert_eq!(
+            FuzzyMatcher::new().fuzzy_match("hello", ""),
+            Some((0, vec![]))
+        );
+    }
+
+    #[test]
+    fn test_no_match() {
+        assert_eq!(positions("hello", "hz"), None);
+        assert_eq!(positions("ba", "ab"), None);
+        assert_eq!(positions("", "a"), None);
+    }
+
+    #[test]
+    fn test_smart_case() {
+        assert_eq!(positions("Hello", "h"), Some(vec![0]));
+        assert_eq!(positions("hello", "H"), None);
+    }
+
+    #[test]
+    fn test_consecutive_bonus() {
+        // first char and boundary bonus for the `a`, then consecutive or gap scores
+        assert_eq!(score("abc", "abc"), 80);
+        assert_eq!(score("axbxc", "abc"), 58);
+    }
+
+    #[test]
+    fn test_word_boundary_bonus() {
+        assert_eq!(score("foo bar", "b"), SCORE_MATCH + BONUS_BOUNDARY);
+        assert_eq!(score("foo-bar", "b"), SCORE_MATCH + BONUS_BOUNDARY);
+        assert_eq!(score("foobar", "b"), SCORE_MATCH);
+    }
+
+    #[test]
+    fn test_camel_case_bonus() {
+        assert_eq!(score("fooBar", "b"), SCORE_MATCH + BONUS_CAMEL_CASE);
+    }
+
+    #[test]
+    fn test_filter_order() {
+        let items = vec![
+            "xfoox".to_string(),
+            "foo".to_string(),
+            "f_o_o".to_string(),
+            "bar".to_string(),
+        ];
+        let indices: Vec<usize> = FuzzyMatcher::new()
+            .filter(&items, "foo")
+            .into_iter()
+            .map(|m| m.index)
+            .collect();
+        assert_eq!(indices, vec![1, 2, 0]);
+    }
+}
@@ -0,0 +1,179 @@
+use crate::cursor::cursor_position;
+use std::convert::{Infallible, TryFrom};
+use std::fmt;
+use std::str::FromStr;
+use winit::dpi::PhysicalPosition;
+use winit::monitor::MonitorHandle;
+use winit::window::Window;
+
+/// Which monitor to open the menu on
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum MonitorSelection {
+    /// The primary monitor
+    #[default]
+    Primary,
+    /// The monitor with the mouse cursor
+    Cursor,
+    /// The monitor with the focused window
+    Focused,
+    /// A monitor by its name, like `DP-1`
+    Name(String),
+    /// A monitor by its position in the list of monitors, starting at 0
+    Index(usize),
+}
+
+impl MonitorSelection {
+    /// Find the selected monitor, falling back to the primary monitor when it can't be found,
+    /// like when the cursor and focused window positions aren't available outside of X11
+    pub(crate) fn select(&self, window: &Window) -> MonitorHandle {
+        let monitor = match self {
+            MonitorSelection::Primary => None,
+            MonitorSelection::Cursor => {
+                cursor_position(window).and_then(|cursor| monitor_containing(window, cursor))
+            }
+            MonitorSelection::Focused => imp::focused_window_center(window)
+                .and_then(|center| monitor_containing(window, center)),
+            MonitorSelection::Name(name) => window
+                .available_monitors()
+                .find(|monitor| monitor.name().as_deref() == Some(name.as_str())),
+            MonitorSelection::Index(index) => window.available_monitors().nth(*index),
+        };
+        monitor.unwrap_or_else(|| window.primary_monitor())
+    }
+
+    /// Select a monitor by index, negative numbers select the monitor with the focus or the
+    /// cursor like in rofi
+    ///
+    /// `-1`, `-2` and `-4` give the monitor with the focused window, `-3` and `-5` the monitor
+    /// with the cursor.
+    pub fn from_index(index: i64) -> Option<Self> {
+        match index {
+            -1 | -2 | -4 => Some(MonitorSelection::Focused),
+            -3 | -5 => Some(MonitorSelection::Cursor),
+            index => usize::try_from(index).ok().map(MonitorSelection::Index),
+        }
+    }
+}
+
+impl FromStr for MonitorSelection {
+    type Err = Infallible;
+
+    /// Parse `primary`, `cursor`, `focused` or an index as for `from_index`, anything else is
+    /// taken as a name
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "primary" => MonitorSelection::Primary,
+            "cursor" | "mouse" => MonitorSelection::Cursor,
+            "focused" | "focus" => MonitorSelection::Focused,
+            _ => match s.parse().ok().and_then(MonitorSelection::from_index) {
+                Some(monitor) => monitor,
+                None => MonitorSelection::Name(s.to_string()),
+            },
+        })
+    }
+}
+
+impl fmt::Display for MonitorSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MonitorSelection::Primary => write!(f, "primary"),
+            MonitorSelection::Cursor => write!(f, "cursor"),
+            MonitorSelection::Focused => write!(f, "focused"),
+            MonitorSelection::Name(name) => write!(f, "{}", name),
+            MonitorSelection::Index(index) => write!(f, "{}", index),
+        }
+    }
+}
+
+/// The monitor containing a position on the screen
+pub(crate) fn monitor_containing(
+    window: &Window,
+    position: PhysicalPosition<i32>,
+) -> Option<MonitorHandle> {
+    window.available_monitors().find(|monitor| {
+        let (origin, size) = (monitor.position(), monitor.size());
+        (origin.x..origin.x + size.width as i32).contains(&position.x)
+            && (origin.y..origin.y + size.height as i32).contains(&position.y)
+    })
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod imp {
+    use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+    use std::mem;
+    use std::os::raw::{c_char, c_int, c_uchar, c_void};
+    use std::ptr;
+    use winit::dpi::PhysicalPosition;
+    use winit::window::Window;
+    use x11_dl::xlib;
+
+    /// The center of the window in `_NET_ACTIVE_WINDOW`, as set by the window manager
+    pub fn focused_window_center(window: &Window) -> Option<PhysicalPosition<i32>> {
+        let handle = match window.raw_window_handle() {
+            RawWindowHandle::Xlib(handle) => handle,
+            _ => return None,
+        };
+        let xlib = xlib::Xlib::open().ok()?;
+        let display = handle.display as *mut xlib::Display;
+
+        unsafe {
+            let root = (xlib.XDefaultRootWindow)(display);
+            let name = b"_NET_ACTIVE_WINDOW\0";
+            let atom = (xlib.XInternAtom)(display, name.as_ptr() as *const c_char, xlib::True);
+            if atom == 0 {
+                return None;
+            }
+
+            let (mut actual_type, mut actual_format) = (0, 0);
+            let (mut items, mut bytes_after) = (0, 0);
+            let mut data: *mut c_uchar = ptr::null_mut();
+            let status = (xlib.XGetWindowProperty)(
+                display,
+                root,
+                atom,
+                0,
+                1,
+                xlib::False,
+                xlib::XA_WINDOW,
+                &mut actual_type,
+                &mut actual_format,
+                &mut items,
+                &mut bytes_after,
+                &mut data,
+            );
+            if status != xlib::Success as c_int || data.is_null() {
+                return None;
+            }
+            // 32 bit properties are returned as an array of longs
+            let active = match (items, actual_format) {
+                (1, 32) => *(data as *const xlib::Window),
+                _ => 0,
+            };
+            (xlib.XFree)(data as *mut c_void);
+            if active == 0 {
+                return None;
+            }
+
+            let mut attributes: xlib::XWindowAttributes = mem::zeroed();
+            if (xlib.XGetWindowAttributes)(display, active, &mut attributes) == 0 {
+                return None;
+            }
+            let (mut x, mut y, mut child) = (0, 0, 0);
+            (xlib.XTranslateCoordinates)(display, active, root, 0, 0, &mut x, &mut y, &mut child);
+            Some(PhysicalPosition::new(
+                x + attributes.width / 2,
+                y + attributes.height / 2,
+            ))
+        }
+    }
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+mod imp {
+    use winit::dpi::PhysicalPosition;
+    use winit::window::Window;
+
+    pub fn focused_window_center(_window: &Window) -> Option<PhysicalPosition<i32>> {
+        None
+    }
+}
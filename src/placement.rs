@@ -0,0 +1,297 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+/// Where the window is placed on the monitor
+///
+/// The position is given as the fraction of the free space left and above the window, so
+/// `(0.5, 0.5)` centers the window and `(1.0, 0.0)` puts it in the top right corner. The
+/// offsets then move the window from that position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub x: f64,
+    pub y: f64,
+    /// Horizontal offset, positive values move the window to the right
+    pub offset_x: Offset,
+    /// Vertical offset, positive values move the window down
+    pub offset_y: Offset,
+    /// Stretch the window over the full width of the monitor, like dmenu
+    pub full_width: bool,
+    /// Open the window at the mouse cursor instead, when the cursor position is available
+    pub at_cursor: bool,
+}
+
+impl Placement {
+    pub fn new(x: f64, y: f64) -> Self {
+        Placement {
+            x,
+            y,
+            offset_x: Offset::default(),
+            offset_y: Offset::default(),
+            full_width: false,
+            at_cursor: false,
+        }
+    }
+
+    /// Place the window at one of the edges, corners or the center of the monitor
+    pub fn anchored(anchor: Anchor) -> Self {
+        let (x, y) = anchor.fractions();
+        Placement::new(x, y)
+    }
+
+    pub fn offset(mut self, x: Offset, y: Offset) -> Self {
+        self.offset_x = x;
+        self.offset_y = y;
+        self
+    }
+
+    pub fn full_width(mut self, full_width: bool) -> Self {
+        self.full_width = full_width;
+        self
+    }
+
+    pub fn at_cursor(mut self, at_cursor: bool) -> Self {
+        self.at_cursor = at_cursor;
+        self
+    }
+
+    pub(crate) fn is_valid(&self) -> bool {
+        (0.0..=1.0).contains(&self.x) && (0.0..=1.0).contains(&self.y)
+    }
+
+    /// The width of the window for full width placement
+    pub fn window_width(&self, monitor_size: PhysicalSize<u32>) -> Option<u32> {
+        match self.full_width {
+            true => Some(monitor_size.width),
+            false => None,
+        }
+    }
+
+    /// The outer position of the window on the monitor, all in physical pixels
+    ///
+    /// When placing at the cursor the window is kept inside of the monitor.
+    pub fn position(
+        &self,
+        monitor_position: PhysicalPosition<i32>,
+        monitor_size: PhysicalSize<u32>,
+        scale_factor: f64,
+        window_size: PhysicalSize<u32>,
+        cursor: Option<PhysicalPosition<i32>>,
+    ) -> PhysicalPosition<i32> {
+        let free_x = monitor_size.width.saturating_sub(window_size.width) as f64;
+        let free_y = monitor_size.height.saturating_sub(window_size.height) as f64;
+        let offset_x = self.offset_x.pixels(monitor_size.width, scale_factor);
+        let offset_y = self.offset_y.pixels(monitor_size.height, scale_factor);
+
+        match cursor.filter(|_| self.at_cursor) {
+            Some(cursor) => {
+                let x = (cursor.x + offset_x - monitor_position.x)
+                    .max(0)
+                    .min(free_x as i32);
+                let y = (cursor.y + offset_y - monitor_position.y)
+                    .max(0)
+                    .min(free_y as i32);
+                PhysicalPosition::new(monitor_position.x + x, monitor_position.y + y)
+            }
+            None => PhysicalPosition::new(
+                monitor_position.x + (free_x * self.x) as i32 + offset_x,
+                monitor_position.y + (free_y * self.y) as i32 + offset_y,
+            ),
+        }
+    }
+
+    /// Where to put a surface that can only be anchored to the edges of the output, like a
+    /// Wayland layer surface, all in logical pixels
+    ///
+    /// Without anchors the compositor centers the surface, anything but the edges and the
+    /// center becomes a margin from the top or left edge, which needs the size of the output.
+    pub fn edge_anchors(&self, size: (u32, u32), output_size: Option<(u32, u32)>) -> EdgeAnchors {
+        let output_width = output_size.map(|(width, _)| width);
+        let output_height = output_size.map(|(_, height)| height);
+        let offset_x = self.offset_x.pixels(output_width.unwrap_or(0), 1.0);
+        let offset_y = self.offset_y.pixels(output_height.unwrap_or(0), 1.0);
+
+        let (left, right, margin_left, margin_right) = match self.full_width {
+            true => (true, true, offset_x, -offset_x),
+            false => edge_anchor(self.x, size.0, output_width, offset_x),
+        };
+        let (top, bottom, margin_top, margin_bottom) =
+            edge_anchor(self.y, size.1, output_height, offset_y);
+
+        EdgeAnchors {
+            top,
+            right,
+            bottom,
+            left,
+            margin: [margin_top, margin_right, margin_bottom, margin_left],
+            width: match self.full_width {
+                true => 0,
+                false => size.0,
+            },
+            height: size.1,
+        }
+    }
+}
+
+/// Whether to anchor to the start and end edge of one axis, and the margins from them
+fn edge_anchor(
+    fraction: f64,
+    size: u32,
+    output_size: Option<u32>,
+    offset: i32,
+) -> (bool, bool, i32, i32) {
+    if fraction <= 0.0 {
+        return (true, false, offset, 0);
+    }
+    if fraction >= 1.0 {
+        return (false, true, 0, -offset);
+    }
+    match output_size {
+        Some(output_size) if fraction != 0.5 || offset != 0 => {
+            let free = output_size.saturating_sub(size) as f64;
+            (true, false, (free * fraction) as i32 + offset, 0)
+        }
+        _ => (false, false, 0, 0),
+    }
+}
+
+/// The edges of the output a surface is anchored to, see `Placement::edge_anchors`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdgeAnchors {
+    pub top: bool,
+    pub right: bool,
+    pub bottom: bool,
+    pub left: bool,
+    /// Distance to the top, right, bottom and left edge, only used for anchored edges
+    pub margin: [i32; 4],
+    /// Width of the surface, 0 when it's stretched between the left and right edge
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for Placement {
+    /// Horizontally centered, one third down the monitor
+    fn default() -> Self {
+        Placement::new(0.5, 1.0 / 3.0)
+    }
+}
+
+/// A point on the monitor to place the window at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// The position as fractions of the free space, as used by `Placement`
+    fn fractions(self) -> (f64, f64) {
+        match self {
+            Anchor::TopLeft => (0.0, 0.0),
+            Anchor::Top => (0.5, 0.0),
+            Anchor::TopRight => (1.0, 0.0),
+            Anchor::Left => (0.0, 0.5),
+            Anchor::Center => (0.5, 0.5),
+            Anchor::Right => (1.0, 0.5),
+            Anchor::BottomLeft => (0.0, 1.0),
+            Anchor::Bottom => (0.5, 1.0),
+            Anchor::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
+impl FromStr for Anchor {
+    type Err = PlacementError;
+
+    /// Parse anchors like `top`, `center` or `bottom-right`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_ascii_lowercase().replace('_', "-").as_str() {
+            "top-left" => Anchor::TopLeft,
+            "top" => Anchor::Top,
+            "top-right" => Anchor::TopRight,
+            "left" => Anchor::Left,
+            "center" => Anchor::Center,
+            "right" => Anchor::Right,
+            "bottom-left" => Anchor::BottomLeft,
+            "bottom" => Anchor::Bottom,
+            "bottom-right" => Anchor::BottomRight,
+            _ => return Err(PlacementError::InvalidAnchor(s.to_string())),
+        })
+    }
+}
+
+/// Distance to move the window from its anchored position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Offset {
+    /// Logical pixels
+    Pixels(i32),
+    /// Percentage of the monitor size
+    Percent(f64),
+}
+
+impl Offset {
+    /// The offset in physical pixels
+    pub(crate) fn pixels(self, monitor_size: u32, scale_factor: f64) -> i32 {
+        match self {
+            Offset::Pixels(pixels) => (pixels as f64 * scale_factor).round() as i32,
+            Offset::Percent(percent) => (monitor_size as f64 * percent / 100.0).round() as i32,
+        }
+    }
+}
+
+impl Default for Offset {
+    fn default() -> Self {
+        Offset::Pixels(0)
+    }
+}
+
+impl FromStr for Offset {
+    type Err = PlacementError;
+
+    /// Parse offsets like `10`, `-10px` or `5%`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || PlacementError::InvalidOffset(s.to_string());
+        let offset = s.trim();
+        match offset.strip_suffix('%') {
+            Some(percent) => percent
+                .trim()
+                .parse()
+                .map(Offset::Percent)
+                .map_err(|_| invalid()),
+            None => offset
+                .trim_end_matches("px")
+                .trim()
+                .parse()
+                .map(Offset::Pixels)
+                .map_err(|_| invalid()),
+        }
+    }
+}
+
+/// Invalid anchor, offset or monitor in the placement config
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlacementError {
+    InvalidAnchor(String),
+    InvalidOffset(String),
+    InvalidMonitor(String),
+}
+
+impl fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlacementError::InvalidAnchor(anchor) => write!(f, "invalid anchor: {}", anchor),
+            PlacementError::InvalidOffset(offset) => write!(f, "invalid offset: {}", offset),
+            PlacementError::InvalidMonitor(monitor) => write!(f, "invalid monitor: {}", monitor),
+        }
+    }
+}
+
+impl Error for PlacementError {}
@@ -0,0 +1,274 @@
+use conrod_core::color::Rgba;
+use conrod_core::image::Map;
+use conrod_core::render::{PrimitiveKind, Primitives};
+use conrod_core::{Point, Rect, Scalar};
+use image::{Pixel, RgbaImage};
+
+/// Draws conrod primitives into an image on the cpu, without needing a gpu or a display
+pub struct Rasterizer {
+    image: RgbaImage,
+    /// Size of the ui in logical pixels
+    win_w: Scalar,
+    win_h: Scalar,
+    dpi_factor: Scalar,
+}
+
+impl Rasterizer {
+    /// Create a rasterizer for a ui of the given logical size
+    pub fn new(win_w: Scalar, win_h: Scalar, dpi_factor: Scalar) -> Self {
+        let width = (win_w * dpi_factor).round() as u32;
+        let height = (win_h * dpi_factor).round() as u32;
+        Rasterizer {
+            image: RgbaImage::new(width, height),
+            win_w,
+            win_h,
+            dpi_factor,
+        }
+    }
+
+    pub fn image(&self) -> &RgbaImage {
+        &self.image
+    }
+
+    pub fn into_image(self) -> RgbaImage {
+        self.image
+    }
+
+    /// Clear the image and draw the primitives, images are looked up in the image map
+    pub fn draw(&mut self, mut primitives: Primitives, image_map: &Map<RgbaImage>) {
+        for pixel in self.image.pixels_mut() {
+            *pixel = image::Rgba::from_channels(0, 0, 0, 255);
+        }
+
+        while let Some(primitive) = primitives.next() {
+            let clip = self.to_pixels(primitive.scizzor);
+            match primitive.kind {
+                PrimitiveKind::Rectangle { color } => {
+                    let rect = self.to_pixels(primitive.rect);
+                    self.fill_rect(rect, &clip, color.to_rgb());
+                }
+                PrimitiveKind::TrianglesSingleColor { color, triangles } => {
+                    for triangle in triangles {
+                        let [a, b, c] = triangle.points();
+                        let points = [self.to_pixel(a), self.to_pixel(b), self.to_pixel(c)];
+                        self.fill_triangle(points, &clip, |_| color);
+                    }
+                }
+                PrimitiveKind::TrianglesMultiColor { triangles } => {
+                    for triangle in triangles {
+                        let [(a, a_color), (b, b_color), (c, c_color)] = triangle.0;
+                        let points = [self.to_pixel(a), self.to_pixel(b), self.to_pixel(c)];
+                        self.fill_triangle(points, &clip, |[wa, wb, wc]| {
+                            let mix = |a: f32, b: f32, c: f32| a * wa + b * wb + c * wc;
+                            Rgba(
+                                mix(a_color.0, b_color.0, c_color.0),
+                                mix(a_color.1, b_color.1, c_color.1),
+                                mix(a_color.2, b_color.2, c_color.2),
+                                mix(a_color.3, b_color.3, c_color.3),
+                            )
+                        });
+                    }
+                }
+                PrimitiveKind::Image {
+                    image_id,
+                    color,
+                    source_rect,
+                } => {
+                    if let Some(image) = image_map.get(&image_id) {
+                        let rect = self.to_pixels(primitive.rect);
+                        let tint = color.map(|color| color.to_rgb());
+                        self.draw_image(image, source_rect, rect, &clip, tint);
+                    }
+                }
+                PrimitiveKind::Text { color, text, .. } => {
+                    let color = color.to_rgb();
+                    for glyph in text.positioned_glyphs(self.dpi_factor as f32) {
+                        if let Some(bounds) = glyph.pixel_bounding_box() {
+                            glyph.draw(|x, y, coverage| {
+                                let x = bounds.min.x + x as i32;
+                                let y = bounds.min.y + y as i32;
+                                if clip.contains(x as Scalar + 0.5, y as Scalar + 0.5) {
+                                    let color = Rgba(color.0, color.1, color.2, color.3 * coverage);
+                                    self.blend(x, y, color);
+                                }
+                            });
+                        }
+                    }
+                }
+                PrimitiveKind::Other(_) => {}
+            }
+        }
+    }
+
+    /// Convert a point in conrod coordinates, centered with the y axis pointing up, to pixels
+    fn to_pixel(&self, [x, y]: Point) -> Point {
+        [
+            (x + self.win_w / 2.0) * self.dpi_factor,
+            (self.win_h / 2.0 - y) * self.dpi_factor,
+        ]
+    }
+
+    fn to_pixels(&self, rect: Rect) -> PixelRect {
+        let [left, top] = self.to_pixel([rect.left(), rect.top()]);
+        let [right, bottom] = self.to_pixel([rect.right(), rect.bottom()]);
+        PixelRect {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    fn fill_rect(&mut self, rect: PixelRect, clip: &PixelRect, color: Rgba) {
+        let rect = rect.intersect(clip);
+        for y in rect.rows(self.image.height()) {
+            for x in rect.columns(self.image.width()) {
+                self.blend(x, y, color);
+            }
+        }
+    }
+
+    /// Fill a triangle, `color` gets the barycentric weights of the pixel center
+    fn fill_triangle<F>(&mut self, [a, b, c]: [Point; 3], clip: &PixelRect, color: F)
+    where
+        F: Fn([f32; 3]) -> Rgba,
+    {
+        let area = edge(a, b, c);
+        if area == 0.0 {
+            return;
+        }
+
+        let bounds = PixelRect {
+            left: a[0].min(b[0]).min(c[0]),
+            top: a[1].min(b[1]).min(c[1]),
+            right: a[0].max(b[0]).max(c[0]),
+            bottom: a[1].max(b[1]).max(c[1]),
+        }
+        .intersect(clip);
+
+        for y in bounds.rows(self.image.height()) {
+            for x in bounds.columns(self.image.width()) {
+                let p = [x as Scalar + 0.5, y as Scalar + 0.5];
+                let wa = edge(b, c, p) / area;
+                let wb = edge(c, a, p) / area;
+                let wc = edge(a, b, p) / area;
+                if wa >= 0.0 && wb >= 0.0 && wc >= 0.0 {
+                    self.blend(x, y, color([wa as f32, wb as f32, wc as f32]));
+                }
+            }
+        }
+    }
+
+    /// Draw the source rect of the image scaled into the target rect, with nearest neighbour
+    /// sampling
+    fn draw_image(
+        &mut self,
+        image: &RgbaImage,
+        source: Option<Rect>,
+        target: PixelRect,
+        clip: &PixelRect,
+        tint: Option<Rgba>,
+    ) {
+        let (source_x, source_y, source_w, source_h) = match source {
+            Some(source) => (source.left(), source.bottom(), source.w(), source.h()),
+            None => (0.0, 0.0, image.width() as Scalar, image.height() as Scalar),
+        };
+        let target_w = target.right - target.left;
+        let target_h = target.bottom - target.top;
+        if target_w <= 0.0 || target_h <= 0.0 {
+            return;
+        }
+
+        let visible = target.intersect(clip);
+        for y in visible.rows(self.image.height()) {
+            for x in visible.columns(self.image.width()) {
+                let u = (x as Scalar + 0.5 - target.left) / target_w;
+                let v = (y as Scalar + 0.5 - target.top) / target_h;
+                let image_x = (source_x + u * source_w) as u32;
+                let image_y = (source_y + v * source_h) as u32;
+                if image_x >= image.width() || image_y >= image.height() {
+                    continue;
+                }
+
+                let (r, g, b, a) = image.get_pixel(image_x, image_y).channels4();
+                let mut color = Rgba(
+                    r as f32 / 255.0,
+                    g as f32 / 255.0,
+                    b as f32 / 255.0,
+                    a as f32 / 255.0,
+                );
+                if let Some(tint) = tint {
+                    color = Rgba(
+                        color.0 * tint.0,
+                        color.1 * tint.1,
+                        color.2 * tint.2,
+                        color.3 * tint.3,
+                    );
+                }
+                self.blend(x, y, color);
+            }
+        }
+    }
+
+    /// Blend the color over the pixel
+    fn blend(&mut self, x: i32, y: i32, Rgba(r, g, b, a): Rgba) {
+        if x < 0 || y < 0 || x as u32 >= self.image.width() || y as u32 >= self.image.height() {
+            return;
+        }
+        let pixel = self.image.get_pixel_mut(x as u32, y as u32);
+        let a = a.clamp(0.0, 1.0);
+        let mix = |src: f32, dst: u8| (src * 255.0 * a + dst as f32 * (1.0 - a)).round() as u8;
+        let (dr, dg, db, da) = pixel.channels4();
+        *pixel = image::Rgba::from_channels(
+            mix(r, dr),
+            mix(g, dg),
+            mix(b, db),
+            (a * 255.0 + da as f32 * (1.0 - a)).round() as u8,
+        );
+    }
+}
+
+/// A rectangle in pixels, with the y axis pointing down
+#[derive(Debug, Clone, Copy)]
+struct PixelRect {
+    left: Scalar,
+    top: Scalar,
+    right: Scalar,
+    bottom: Scalar,
+}
+
+impl PixelRect {
+    fn intersect(&self, other: &PixelRect) -> PixelRect {
+        PixelRect {
+            left: self.left.max(other.left),
+            top: self.top.max(other.top),
+            right: self.right.min(other.right),
+            bottom: self.bottom.min(other.bottom),
+        }
+    }
+
+    fn contains(&self, x: Scalar, y: Scalar) -> bool {
+        x >= self.left && x < self.right && y >= self.top && y < self.bottom
+    }
+
+    /// The rows with their pixel center inside the rect
+    fn rows(&self, height: u32) -> std::ops::Range<i32> {
+        pixel_range(self.top, self.bottom, height)
+    }
+
+    /// The columns with their pixel center inside the rect
+    fn columns(&self, width: u32) -> std::ops::Range<i32> {
+        pixel_range(self.left, self.right, width)
+    }
+}
+
+fn pixel_range(start: Scalar, end: Scalar, size: u32) -> std::ops::Range<i32> {
+    let start = (start - 0.5).ceil().max(0.0) as i32;
+    let end = ((end - 0.5).ceil().max(0.0) as i32).min(size as i32);
+    start..end.max(start)
+}
+
+/// Twice the signed area of the triangle `a`, `b`, `p`
+fn edge(a: Point, b: Point, p: Point) -> Scalar {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
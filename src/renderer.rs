@@ -1,12 +1,84 @@
+use crate::error::Error;
+use crate::software::SoftwareRenderer;
 use conrod_core::image::Map;
 use conrod_core::render::Primitives;
+use conrod_core::text::rt::gpu_cache::CacheWriteErr;
 use conrod_wgpu::Image;
+use image::RgbaImage;
 use wgpu::{Device, Queue, Surface, SwapChain, SwapChainDescriptor};
 use winit::dpi::PhysicalSize;
 use winit::window::Window;
 
 const MSAA_SAMPLES: u32 = 1;
 
+/// Find a gpu to render with, if any is available
+pub fn request_adapter() -> Option<wgpu::Adapter> {
+    let adapter_opts = wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::LowPower,
+        backends: wgpu::BackendBit::PRIMARY,
+    };
+    wgpu::Adapter::request(&adapter_opts)
+}
+
+/// Renders the ui to the window, on the gpu if possible and in software otherwise
+pub enum WindowRenderer {
+    Gpu {
+        renderer: Box<Renderer>,
+        image_map: Map<Image>,
+    },
+    Software {
+        renderer: Box<SoftwareRenderer>,
+        image_map: Map<RgbaImage>,
+    },
+}
+
+impl WindowRenderer {
+    pub fn new(window: &Window, adapter: Option<wgpu::Adapter>) -> Result<Self, Error> {
+        Ok(match adapter {
+            Some(adapter) => WindowRenderer::Gpu {
+                renderer: Box::new(Renderer::new(window, adapter)),
+                image_map: Map::new(),
+            },
+            None => WindowRenderer::Software {
+                renderer: Box::new(SoftwareRenderer::new(window).ok_or(Error::NoRenderer)?),
+                image_map: Map::new(),
+            },
+        })
+    }
+
+    /// Add an image to the image map of the renderer
+    pub fn upload_image(&mut self, image: RgbaImage) -> conrod_core::image::Id {
+        match self {
+            WindowRenderer::Gpu {
+                renderer,
+                image_map,
+            } => image_map.insert(renderer.upload_image(image)),
+            WindowRenderer::Software { image_map, .. } => image_map.insert(image),
+        }
+    }
+
+    pub fn render(&mut self, primitives: Primitives, window: &Window) -> Result<(), Error> {
+        match self {
+            WindowRenderer::Gpu {
+                renderer,
+                image_map,
+            } => renderer.render(primitives, window, image_map)?,
+            WindowRenderer::Software {
+                renderer,
+                image_map,
+            } => renderer.render(primitives, window, image_map),
+        }
+        Ok(())
+    }
+
+    pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
+        match self {
+            WindowRenderer::Gpu { renderer, .. } => renderer.resize(new_size),
+            WindowRenderer::Software { renderer, .. } => renderer.resize(new_size),
+        }
+    }
+}
+
 pub struct Renderer {
     pub wgpu_renderer: conrod_wgpu::Renderer,
     pub swap_chain: SwapChain,
@@ -19,16 +91,11 @@ pub struct Renderer {
 }
 
 impl Renderer {
-    pub fn new(window: &Window) -> Self {
+    pub fn new(window: &Window, adapter: wgpu::Adapter) -> Self {
         let size = window.inner_size();
         let surface = wgpu::Surface::create(window);
 
-        // Select an adapter and gpu device.
-        let adapter_opts = wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::LowPower,
-            backends: wgpu::BackendBit::PRIMARY,
-        };
-        let adapter = wgpu::Adapter::request(&adapter_opts).unwrap();
+        // Select a gpu device.
         let extensions = wgpu::Extensions {
             anisotropic_filtering: false,
         };
@@ -66,7 +133,12 @@ impl Renderer {
         }
     }
 
-    pub fn render(&mut self, primitives: Primitives, window: &Window, image_map: &Map<Image>) {
+    pub fn render(
+        &mut self,
+        primitives: Primitives,
+        window: &Window,
+        image_map: &Map<Image>,
+    ) -> Result<(), CacheWriteErr> {
         // The window frame that we will draw to.
         let frame = self.swap_chain.get_next_texture();
 
@@ -80,8 +152,7 @@ impl Renderer {
         let viewport = [0.0, 0.0, win_w, win_h];
         if let Some(cmd) = self
             .wgpu_renderer
-            .fill(image_map, viewport, scale_factor, primitives)
-            .unwrap()
+            .fill(image_map, viewport, scale_factor, primitives)?
         {
             cmd.load_buffer_and_encode(&self.device, &mut encoder);
         }
@@ -132,6 +203,61 @@ impl Renderer {
         }
 
         self.queue.submit(&[encoder.finish()]);
+        Ok(())
+    }
+
+    /// Upload an image to the gpu so it can be added to the image map
+    pub fn upload_image(&mut self, image: RgbaImage) -> Image {
+        let (width, height) = image.dimensions();
+        let texture_format = wgpu::TextureFormat::Rgba8UnormSrgb;
+        let extent = wgpu::Extent3d {
+            width,
+            height,
+            depth: 1,
+        };
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            size: extent,
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: texture_format,
+            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+        });
+
+        let data = image.into_raw();
+        let buffer = self
+            .device
+            .create_buffer_mapped(data.len(), wgpu::BufferUsage::COPY_SRC)
+            .fill_from_slice(&data);
+
+        let cmd_encoder_desc = wgpu::CommandEncoderDescriptor { todo: 0 };
+        let mut encoder = self.device.create_command_encoder(&cmd_encoder_desc);
+        let buffer_copy_view = wgpu::BufferCopyView {
+            buffer: &buffer,
+            offset: 0,
+            row_pitch: width * 4,
+            image_height: height,
+        };
+        let texture_copy_view = wgpu::TextureCopyView {
+            texture: &texture,
+            mip_level: 0,
+            array_layer: 0,
+            origin: wgpu::Origin3d {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+        };
+        encoder.copy_buffer_to_texture(buffer_copy_view, texture_copy_view, extent);
+        self.queue.submit(&[encoder.finish()]);
+
+        Image {
+            texture,
+            texture_format,
+            width,
+            height,
+        }
     }
 
     pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
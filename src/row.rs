@@ -0,0 +1,396 @@
+// the conrod derives implement their traits from inside a const block
+#![allow(non_local_definitions)]
+
+use crate::markup::Span;
+use conrod_core::text::{font, GlyphId};
+use conrod_core::{
+    image, widget, widget_ids, Color, Colorable, FontSize, Positionable, Scalar, Sizeable, Widget,
+    WidgetCommon, WidgetStyle,
+};
+
+const PADDING: Scalar = 2.0;
+
+/// A single row in the result list, rendering the matched characters highlighted
+///
+/// When a subtitle is set the title and subtitle are rendered on two lines.
+#[derive(WidgetCommon)]
+pub struct ItemRow<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    title: &'a str,
+    markup: Option<&'a [Span]>,
+    highlights: &'a [usize],
+    subtitle: Option<&'a str>,
+    icon: Option<image::Id>,
+    icon_column: bool,
+    marked: bool,
+    fallback_fonts: &'a [font::Id],
+    style: Style,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, WidgetStyle)]
+pub struct Style {
+    /// Background color of the row
+    #[conrod(default = "theme.background_color")]
+    pub color: Option<Color>,
+    #[conrod(default = "theme.label_color")]
+    pub label_color: Option<Color>,
+    /// Color for the characters that matched the search
+    #[conrod(default = "conrod_core::color::LIGHT_BLUE")]
+    pub highlight_color: Option<Color>,
+    #[conrod(default = "theme.font_size_medium")]
+    pub font_size: Option<FontSize>,
+    #[conrod(default = "theme.font_id")]
+    pub font_id: Option<Option<font::Id>>,
+    /// Font for bold text and the characters that matched the search
+    #[conrod(default = "None")]
+    pub bold_font_id: Option<Option<font::Id>>,
+    #[conrod(default = "None")]
+    pub italic_font_id: Option<Option<font::Id>>,
+    #[conrod(default = "None")]
+    pub bold_italic_font_id: Option<Option<font::Id>>,
+    #[conrod(default = "theme.label_color.alpha(0.6)")]
+    pub subtitle_color: Option<Color>,
+    #[conrod(default = "theme.font_size_small")]
+    pub subtitle_font_size: Option<FontSize>,
+    /// Color for the marker and text of marked rows
+    #[conrod(default = "conrod_core::color::LIGHT_ORANGE")]
+    pub mark_color: Option<Color>,
+}
+
+widget_ids! {
+    struct Ids {
+        background,
+        mark,
+        icon,
+        segments[],
+        subtitle_segments[],
+    }
+}
+
+pub struct State {
+    ids: Ids,
+}
+
+impl<'a> ItemRow<'a> {
+    /// Create a row for the title, `highlights` are the sorted character offsets to highlight
+    pub fn new(title: &'a str, highlights: &'a [usize]) -> Self {
+        ItemRow {
+            common: widget::CommonBuilder::default(),
+            title,
+            markup: None,
+            highlights,
+            subtitle: None,
+            icon: None,
+            icon_column: false,
+            marked: false,
+            fallback_fonts: &[],
+            style: Style::default(),
+        }
+    }
+
+    /// Show the row as marked in multi select mode
+    pub fn marked(mut self, marked: bool) -> Self {
+        self.marked = marked;
+        self
+    }
+
+    /// Show an icon at the start of the row
+    pub fn icon(mut self, icon: image::Id) -> Self {
+        self.icon = Some(icon);
+        self.icon_column = true;
+        self
+    }
+
+    /// Reserve space for an icon even if the row has no icon, to align it with rows that do
+    pub fn icon_column(mut self, icon_column: bool) -> Self {
+        self.icon_column = icon_column;
+        self
+    }
+
+    /// Styled spans to show instead of the plain title, their text should be the title
+    pub fn markup(mut self, spans: &'a [Span]) -> Self {
+        self.markup = Some(spans);
+        self
+    }
+
+    pub fn subtitle(mut self, subtitle: &'a str) -> Self {
+        self.subtitle = Some(subtitle);
+        self
+    }
+
+    /// Fonts for the characters missing from the title and subtitle fonts, in order
+    pub fn fallback_fonts(mut self, fonts: &'a [font::Id]) -> Self {
+        self.fallback_fonts = fonts;
+        self
+    }
+
+    pub fn font_size(mut self, size: FontSize) -> Self {
+        self.style.font_size = Some(size);
+        self
+    }
+
+    pub fn font_id(mut self, font_id: font::Id) -> Self {
+        self.style.font_id = Some(Some(font_id));
+        self
+    }
+
+    pub fn bold_font_id(mut self, font_id: font::Id) -> Self {
+        self.style.bold_font_id = Some(Some(font_id));
+        self
+    }
+
+    pub fn italic_font_id(mut self, font_id: font::Id) -> Self {
+        self.style.italic_font_id = Some(Some(font_id));
+        self
+    }
+
+    pub fn bold_italic_font_id(mut self, font_id: font::Id) -> Self {
+        self.style.bold_italic_font_id = Some(Some(font_id));
+        self
+    }
+
+    pub fn subtitle_font_size(mut self, size: FontSize) -> Self {
+        self.style.subtitle_font_size = Some(size);
+        self
+    }
+}
+
+impl<'a> Colorable for ItemRow<'a> {
+    fn color(mut self, color: Color) -> Self {
+        self.style.color = Some(color);
+        self
+    }
+}
+
+impl<'a> Widget for ItemRow<'a> {
+    type State = State;
+    type Style = Style;
+    type Event = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        State {
+            ids: Ids::new(id_gen),
+        }
+    }
+
+    fn style(&self) -> Self::Style {
+        self.style
+    }
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            rect,
+            ui,
+            style,
+            ..
+        } = args;
+
+        widget::Rectangle::fill(rect.dim())
+            .xy(rect.xy())
+            .color(style.color(&ui.theme))
+            .parent(id)
+            .graphics_for(id)
+            .set(state.ids.background, ui);
+
+        // marked rows get a bar in the padding before the content
+        if self.marked {
+            widget::Rectangle::fill([PADDING, rect.h()])
+                .mid_left_of(id)
+                .color(style.mark_color(&ui.theme))
+                .parent(id)
+                .graphics_for(id)
+                .set(state.ids.mark, ui);
+        }
+
+        // icons are square, filling the height of the row
+        let icon_size = rect.h() - 2.0 * PADDING;
+        if let Some(icon) = self.icon {
+            widget::Image::new(icon)
+                .w_h(icon_size, icon_size)
+                .mid_left_with_margin_on(id, PADDING)
+                .parent(id)
+                .graphics_for(id)
+                .set(state.ids.icon, ui);
+        }
+        let text_margin = match self.icon_column {
+            true => icon_size + 2.0 * PADDING,
+            false => PADDING,
+        };
+
+        let font_size = style.font_size(&ui.theme);
+        let font_id = style.font_id(&ui.theme);
+        let bold_font_id = style.bold_font_id(&ui.theme).or(font_id);
+        let italic_font_id = style.italic_font_id(&ui.theme).or(font_id);
+        let bold_italic_font_id = style.bold_italic_font_id(&ui.theme).or(bold_font_id);
+
+        let plain = [Span::plain(self.title)];
+        let spans = self.markup.unwrap_or(&plain);
+
+        // each segment is split further in runs per font, so characters missing from the font
+        // are drawn with the fallback fonts
+        let mut pieces = Vec::new();
+        for (text, span, highlighted) in segments(spans, self.highlights) {
+            // highlighted characters are drawn bold, keeping the style of the markup otherwise
+            let font_id = match (span.bold || highlighted, span.italic) {
+                (false, false) => font_id,
+                (true, false) => bold_font_id,
+                (false, true) => italic_font_id,
+                (true, true) => bold_italic_font_id,
+            };
+            let color = if highlighted {
+                style.highlight_color(&ui.theme)
+            } else if self.marked {
+                style.mark_color(&ui.theme)
+            } else {
+                span.color.unwrap_or_else(|| style.label_color(&ui.theme))
+            };
+            for (run, font_id) in font_runs(text, font_id, self.fallback_fonts, &ui.fonts) {
+                pieces.push((run, color, font_id));
+            }
+        }
+        let subtitle_runs = self
+            .subtitle
+            .map(|subtitle| font_runs(subtitle, font_id, self.fallback_fonts, &ui.fonts))
+            .unwrap_or_default();
+
+        if state.ids.segments.len() < pieces.len() {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| state.ids.segments.resize(pieces.len(), id_gen));
+        }
+        if state.ids.subtitle_segments.len() < subtitle_runs.len() {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| {
+                state
+                    .ids
+                    .subtitle_segments
+                    .resize(subtitle_runs.len(), id_gen)
+            });
+        }
+
+        let mut previous = None;
+        for (&(text, color, font_id), &segment_id) in pieces.iter().zip(state.ids.segments.iter()) {
+            let mut segment = widget::Text::new(text)
+                .font_size(font_size)
+                .color(color)
+                .no_line_wrap()
+                .parent(id)
+                .graphics_for(id);
+            if let Some(font_id) = font_id {
+                segment = segment.font_id(font_id);
+            }
+            match (previous, self.subtitle) {
+                (Some(previous), _) => segment
+                    .right_from(previous, 0.0)
+                    .align_middle_y_of(previous),
+                (None, Some(_)) => segment.top_left_with_margins_on(id, PADDING, text_margin),
+                (None, None) => segment.mid_left_with_margin_on(id, text_margin),
+            }
+            .set(segment_id, ui);
+
+            previous = Some(segment_id);
+        }
+
+        let mut previous = None;
+        let subtitle_ids = state.ids.subtitle_segments.iter();
+        for (&(text, font_id), &segment_id) in subtitle_runs.iter().zip(subtitle_ids) {
+            let mut segment = widget::Text::new(text)
+                .font_size(style.subtitle_font_size(&ui.theme))
+                .color(style.subtitle_color(&ui.theme))
+                .no_line_wrap()
+                .parent(id)
+                .graphics_for(id);
+            if let Some(font_id) = font_id {
+                segment = segment.font_id(font_id);
+            }
+            match previous {
+                Some(previous) => segment
+                    .right_from(previous, 0.0)
+                    .align_middle_y_of(previous),
+                None => segment.bottom_left_with_margins_on(id, PADDING, text_margin),
+            }
+            .set(segment_id, ui);
+
+            previous = Some(segment_id);
+        }
+    }
+}
+
+/// Split the styled text in runs of highlighted and non highlighted characters
+fn segments<'a>(spans: &'a [Span], highlights: &[usize]) -> Vec<(&'a str, &'a Span, bool)> {
+    let mut segments = Vec::new();
+    let mut char_index = 0;
+
+    for span in spans {
+        let text = span.text.as_str();
+        let mut start = 0;
+        let mut current = false;
+
+        for (byte_index, _) in text.char_indices() {
+            let highlighted = highlights.binary_search(&char_index).is_ok();
+            if highlighted != current {
+                if byte_index > start {
+                    segments.push((&text[start..byte_index], span, current));
+                }
+                start = byte_index;
+                current = highlighted;
+            }
+            char_index += 1;
+        }
+
+        if start < text.len() {
+            segments.push((&text[start..], span, current));
+        }
+    }
+
+    segments
+}
+
+/// Split the text in runs that can be drawn with a single font, using the first font of the
+/// chain that has a glyph for each character
+///
+/// Whitespace stays in the current run, characters no font has are drawn with the main font.
+fn font_runs<'t>(
+    text: &'t str,
+    font_id: Option<font::Id>,
+    fallback: &[font::Id],
+    fonts: &font::Map,
+) -> Vec<(&'t str, Option<font::Id>)> {
+    let main = match font_id {
+        Some(font_id) => font_id,
+        None => return vec![(text, None)],
+    };
+    let has_glyph = |font_id: font::Id, c: char| {
+        fonts
+            .get(font_id)
+            .is_some_and(|font| font.glyph(c).id() != GlyphId(0))
+    };
+
+    let mut runs = Vec::new();
+    let mut start = 0;
+    let mut current = main;
+    for (index, c) in text.char_indices() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let font_id = std::iter::once(main)
+            .chain(fallback.iter().cloned())
+            .find(|&font_id| has_glyph(font_id, c))
+            .unwrap_or(main);
+        if font_id != current {
+            if index > start {
+                runs.push((&text[start..index], Some(current)));
+            }
+            start = index;
+            current = font_id;
+        }
+    }
+
+    if start < text.len() {
+        runs.push((&text[start..], Some(current)));
+    }
+
+    runs
+}
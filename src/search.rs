@@ -0,0 +1,377 @@
+use futures::channel::mpsc::{unbounded, UnboundedSender};
+use futures::executor::block_on;
+use futures::{select, FutureExt, Stream, StreamExt};
+use futures_timer::Delay;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, SendError, Sender};
+use std::sync::Arc;
+use std::time::Duration;
+use winit::event_loop::EventLoopProxy;
+
+/// Maximum number of items from a stream to send to the ui at once
+const STREAM_BATCH_SIZE: usize = 256;
+
+/// How the search callback is run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMode {
+    /// Run the search on a background thread once no new query has been typed for the duration
+    Debounced(Duration),
+    /// Run the search on the ui thread for every query, without any delay
+    ///
+    /// Only suitable for searches that finish quickly, like filtering an in memory list.
+    Immediate,
+}
+
+impl Default for SearchMode {
+    fn default() -> Self {
+        SearchMode::Debounced(Duration::from_millis(100))
+    }
+}
+
+/// Token that is cancelled once the query for a search is superseded by a newer query
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed)
+    }
+}
+
+/// An item from a search callback, with the characters of its title that matched when the
+/// callback reported them
+pub(crate) type SearchResult<Item> = (Item, Option<Vec<usize>>);
+
+/// Results for items without matched characters
+pub(crate) fn without_positions<Item>(items: Vec<Item>) -> Vec<SearchResult<Item>> {
+    items.into_iter().map(|item| (item, None)).collect()
+}
+
+pub(crate) enum SearchMessage<Item> {
+    /// A batch of results, to be appended to the results already received for the query
+    Results {
+        generation: u64,
+        items: Vec<SearchResult<Item>>,
+    },
+    /// The search for the query is done
+    Finished { generation: u64 },
+}
+
+impl<Item> SearchMessage<Item> {
+    fn generation(&self) -> u64 {
+        match self {
+            SearchMessage::Results { generation, .. } => *generation,
+            SearchMessage::Finished { generation } => *generation,
+        }
+    }
+}
+
+/// Sink for a search to send its results to, results can be send in multiple batches
+pub struct ResultSink<Item> {
+    generation: u64,
+    token: CancellationToken,
+    tx: Sender<SearchMessage<Item>>,
+    proxy: Option<EventLoopProxy<()>>,
+}
+
+impl<Item> ResultSink<Item> {
+    /// Send a batch of results to the menu, the results are shown directly
+    ///
+    /// Returns false if the search has been cancelled and no more results should be send
+    pub fn send(&self, items: Vec<Item>) -> bool {
+        self.send_results(without_positions(items))
+    }
+
+    /// Send a batch of results together with the character (not byte) offsets in their titles
+    /// that matched the query
+    ///
+    /// These are highlighted instead of the characters found by the built in fuzzy matcher,
+    /// which doesn't know how the search matched.
+    pub fn send_matches(&self, items: Vec<(Item, Vec<usize>)>) -> bool {
+        let results = items
+            .into_iter()
+            .map(|(item, positions)| (item, Some(positions)))
+            .collect();
+        self.send_results(results)
+    }
+
+    fn send_results(&self, items: Vec<SearchResult<Item>>) -> bool {
+        if self.token.is_cancelled() {
+            return false;
+        }
+
+        let message = SearchMessage::Results {
+            generation: self.generation,
+            items,
+        };
+        if self.tx.send(message).is_err() {
+            return false;
+        }
+
+        // wakeup the event loop
+        if let Some(proxy) = &self.proxy {
+            let _ = proxy.send_event(());
+        }
+        true
+    }
+}
+
+/// Run the search on the current thread, collecting all batches
+pub(crate) fn search_immediate<Item, Search>(
+    search: &Search,
+    query: String,
+) -> Vec<SearchResult<Item>>
+where
+    Search: Fn(String, &CancellationToken, &ResultSink<Item>),
+{
+    let (tx, rx) = channel();
+    let token = CancellationToken::new();
+    let sink = ResultSink {
+        generation: 0,
+        token: token.clone(),
+        tx,
+        proxy: None,
+    };
+    search(query, &token, &sink);
+    drop(sink);
+
+    rx.into_iter()
+        .flat_map(|message| match message {
+            SearchMessage::Results { items, .. } => items,
+            SearchMessage::Finished { .. } => Vec::new(),
+        })
+        .collect()
+}
+
+/// How the items for a query are found
+pub(crate) enum SearchBackend<Item> {
+    /// Filter the items set on the app with the built in matcher
+    Static,
+    /// Run the search on the ui thread
+    Immediate(Box<dyn Fn(String) -> Vec<SearchResult<Item>>>),
+    /// Run the search in the background
+    Worker(SearchWorker<Item>),
+}
+
+impl<Item: Send + 'static> SearchBackend<Item> {
+    /// Get the next message from a search running in the background
+    pub fn try_recv(&mut self) -> Option<SearchMessage<Item>> {
+        match self {
+            SearchBackend::Worker(worker) => worker.try_recv(),
+            _ => None,
+        }
+    }
+
+    /// Whether results are still expected from a search running in the background
+    #[cfg(feature = "layer-shell")]
+    pub fn is_searching(&self) -> bool {
+        match self {
+            SearchBackend::Worker(worker) => worker.searching,
+            _ => false,
+        }
+    }
+}
+
+struct Query {
+    generation: u64,
+    query: String,
+    token: CancellationToken,
+}
+
+enum QuerySender {
+    Thread(Sender<Query>),
+    Async(UnboundedSender<Query>),
+}
+
+/// Runs the search callback on a background thread
+pub(crate) struct SearchWorker<Item> {
+    query_tx: QuerySender,
+    results_rx: Receiver<SearchMessage<Item>>,
+    generation: u64,
+    token: CancellationToken,
+    /// The search for the latest query hasn't finished yet
+    #[cfg_attr(not(feature = "layer-shell"), allow(dead_code))]
+    searching: bool,
+}
+
+impl<Item: Send + 'static> SearchWorker<Item> {
+    pub fn spawn<Search>(
+        search: Search,
+        proxy: Option<EventLoopProxy<()>>,
+        debounce: Duration,
+    ) -> Self
+    where
+        Search: Fn(String, &CancellationToken, &ResultSink<Item>) + Send + 'static,
+    {
+        let (query_tx, query_rx) = channel::<Query>();
+        let (results_tx, results_rx) = channel();
+
+        std::thread::spawn(move || {
+            // first block for the first query
+            while let Ok(mut query) = query_rx.recv() {
+                // then wait until there is no new query set for some duration
+                while let Ok(new_query) = query_rx.recv_timeout(debounce) {
+                    query = new_query;
+                }
+
+                let sink = ResultSink {
+                    generation: query.generation,
+                    token: query.token.clone(),
+                    tx: results_tx.clone(),
+                    proxy: proxy.clone(),
+                };
+                search(query.query, &query.token, &sink);
+
+                let finished = SearchMessage::Finished {
+                    generation: query.generation,
+                };
+                if results_tx.send(finished).is_err() {
+                    break;
+                }
+
+                // wakeup the event loop
+                if let Some(proxy) = &proxy {
+                    let _ = proxy.send_event(());
+                }
+            }
+        });
+
+        SearchWorker {
+            query_tx: QuerySender::Thread(query_tx),
+            results_rx,
+            generation: 0,
+            token: CancellationToken::new(),
+            searching: false,
+        }
+    }
+
+    /// Run a search that returns a stream of items on an executor in a background thread
+    ///
+    /// Once a new query is received the stream for the previous query is dropped.
+    pub fn spawn_stream<Search, S>(
+        search: Search,
+        proxy: Option<EventLoopProxy<()>>,
+        debounce: Duration,
+    ) -> Self
+    where
+        Search: Fn(String) -> S + Send + 'static,
+        S: Stream<Item = Item> + 'static,
+    {
+        let (query_tx, mut query_rx) = unbounded::<Query>();
+        let (results_tx, results_rx) = channel();
+
+        std::thread::spawn(move || {
+            block_on(async move {
+                // first wait for the first query
+                let mut next_query = query_rx.next().await;
+
+                while let Some(mut query) = next_query.take() {
+                    // then wait until there is no new query set for some duration
+                    loop {
+                        let mut delay = Delay::new(debounce).fuse();
+                        select! {
+                            new_query = query_rx.next() => match new_query {
+                                Some(new_query) => query = new_query,
+                                None => return,
+                            },
+                            _ = delay => break,
+                        }
+                    }
+
+                    let generation = query.generation;
+                    let mut results = Box::pin(search(query.query))
+                        .ready_chunks(STREAM_BATCH_SIZE)
+                        .fuse();
+
+                    // send results until the stream ends or a new query is received
+                    loop {
+                        select! {
+                            new_query = query_rx.next() => {
+                                match new_query {
+                                    Some(new_query) => next_query = Some(new_query),
+                                    None => return,
+                                }
+                                break;
+                            },
+                            items = results.next() => {
+                                let message = match items {
+                                    Some(items) => SearchMessage::Results {
+                                        generation,
+                                        items: without_positions(items),
+                                    },
+                                    None => SearchMessage::Finished { generation },
+                                };
+                                let finished = matches!(message, SearchMessage::Finished { .. });
+                                if results_tx.send(message).is_err() {
+                                    return;
+                                }
+
+                                // wakeup the event loop
+                                if let Some(proxy) = &proxy {
+                                    let _ = proxy.send_event(());
+                                }
+
+                                if finished {
+                                    next_query = query_rx.next().await;
+                                    break;
+                                }
+                            },
+                        }
+                    }
+                }
+            })
+        });
+
+        SearchWorker {
+            query_tx: QuerySender::Async(query_tx),
+            results_rx,
+            generation: 0,
+            token: CancellationToken::new(),
+            searching: false,
+        }
+    }
+
+    /// Start searching for a new query, cancelling the running search
+    pub fn search(&mut self, query: String) -> Result<(), SendError<String>> {
+        self.token.cancel();
+        self.token = CancellationToken::new();
+        self.generation += 1;
+
+        let query = Query {
+            generation: self.generation,
+            query,
+            token: self.token.clone(),
+        };
+        let sent = match &self.query_tx {
+            QuerySender::Thread(query_tx) => query_tx
+                .send(query)
+                .map_err(|SendError(query)| SendError(query.query)),
+            QuerySender::Async(query_tx) => query_tx
+                .unbounded_send(query)
+                .map_err(|err| SendError(err.into_inner().query)),
+        };
+        self.searching = sent.is_ok();
+        sent
+    }
+
+    /// Get the next message for the latest query, messages for older queries are dropped
+    pub fn try_recv(&mut self) -> Option<SearchMessage<Item>> {
+        while let Ok(message) = self.results_rx.try_recv() {
+            if message.generation() == self.generation {
+                if let SearchMessage::Finished { .. } = message {
+                    self.searching = false;
+                }
+                return Some(message);
+            }
+        }
+        None
+    }
+}
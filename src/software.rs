@@ -0,0 +1,306 @@
+use crate::raster::Rasterizer;
+use conrod_core::image::Map;
+use conrod_core::render::Primitives;
+use image::RgbaImage;
+use winit::dpi::PhysicalSize;
+use winit::window::Window;
+
+/// Renders the ui on the cpu and copies the result to the window
+///
+/// Used when no gpu is available, only supported on X11 and Windows, on Wayland the
+/// window is opened through XWayland.
+pub struct SoftwareRenderer {
+    presenter: Presenter,
+    size: PhysicalSize<u32>,
+}
+
+impl SoftwareRenderer {
+    pub fn new(window: &Window) -> Option<Self> {
+        Some(SoftwareRenderer {
+            presenter: Presenter::new(window)?,
+            size: window.inner_size(),
+        })
+    }
+
+    pub fn render(&mut self, primitives: Primitives, window: &Window, image_map: &Map<RgbaImage>) {
+        let scale_factor = window.scale_factor();
+        let size = self.size.to_logical::<f64>(scale_factor);
+        let mut rasterizer = Rasterizer::new(size.width, size.height, scale_factor);
+        rasterizer.draw(primitives, image_map);
+        self.presenter.present(rasterizer.image());
+    }
+
+    pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
+        self.size = new_size;
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+use x11::Presenter;
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod x11 {
+    use image::{Pixel, RgbaImage};
+    use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+    use std::mem;
+    use std::os::raw::{c_char, c_int, c_ulong, c_void};
+    use std::ptr;
+    use winit::window::Window;
+    use x11_dl::xlib;
+
+    /// Copies images to an X11 window with `XPutImage`
+    pub struct Presenter {
+        xlib: xlib::Xlib,
+        display: *mut xlib::Display,
+        window: xlib::Window,
+        gc: xlib::GC,
+        visual: *mut xlib::Visual,
+        depth: c_int,
+        red: Channel,
+        green: Channel,
+        blue: Channel,
+    }
+
+    /// Where a color channel is stored in the pixels of a visual
+    struct Channel {
+        shift: u32,
+        bits: u32,
+    }
+
+    impl Channel {
+        /// The channel for a mask of the visual, which has to be a single run of at most 8 bits
+        fn from_mask(mask: c_ulong) -> Option<Self> {
+            if mask == 0 {
+                return None;
+            }
+            let shift = mask.trailing_zeros();
+            let bits = (mask >> shift).count_ones();
+            match bits <= 8 && mask >> shift == (1 << bits) - 1 {
+                true => Some(Channel { shift, bits }),
+                false => None,
+            }
+        }
+
+        fn pack(&self, value: u8) -> c_ulong {
+            ((value >> (8 - self.bits)) as c_ulong) << self.shift
+        }
+    }
+
+    impl Presenter {
+        /// Create a presenter for the visual of the window, `None` if the visual isn't supported
+        pub fn new(window: &Window) -> Option<Self> {
+            let handle = match window.raw_window_handle() {
+                RawWindowHandle::Xlib(handle) => handle,
+                _ => return None,
+            };
+            let xlib = xlib::Xlib::open().ok()?;
+            let display = handle.display as *mut xlib::Display;
+
+            unsafe {
+                let mut attributes: xlib::XWindowAttributes = mem::zeroed();
+                if (xlib.XGetWindowAttributes)(display, handle.window, &mut attributes) == 0 {
+                    return None;
+                }
+                let visual = attributes.visual;
+                let depth = attributes.depth;
+                // the pixel values are written directly, which needs a visual without a color map
+                if visual.is_null() || (*visual).class != xlib::TrueColor {
+                    return None;
+                }
+                let red = Channel::from_mask((*visual).red_mask)?;
+                let green = Channel::from_mask((*visual).green_mask)?;
+                let blue = Channel::from_mask((*visual).blue_mask)?;
+
+                // check that the pixels of the visual are whole bytes
+                let ximage = (xlib.XCreateImage)(
+                    display,
+                    visual,
+                    depth as u32,
+                    xlib::ZPixmap,
+                    0,
+                    ptr::null_mut(),
+                    1,
+                    1,
+                    32,
+                    0,
+                );
+                if ximage.is_null() {
+                    return None;
+                }
+                let bits_per_pixel = (*ximage).bits_per_pixel;
+                (xlib.XFree)(ximage as *mut c_void);
+                if ![16, 24, 32].contains(&bits_per_pixel) {
+                    return None;
+                }
+
+                let gc = (xlib.XCreateGC)(display, handle.window, 0, ptr::null_mut());
+                Some(Presenter {
+                    xlib,
+                    display,
+                    window: handle.window,
+                    gc,
+                    visual,
+                    depth,
+                    red,
+                    green,
+                    blue,
+                })
+            }
+        }
+
+        pub fn present(&mut self, image: &RgbaImage) {
+            let (width, height) = image.dimensions();
+
+            unsafe {
+                let ximage = (self.xlib.XCreateImage)(
+                    self.display,
+                    self.visual,
+                    self.depth as u32,
+                    xlib::ZPixmap,
+                    0,
+                    ptr::null_mut(),
+                    width,
+                    height,
+                    32,
+                    0,
+                );
+                if ximage.is_null() {
+                    return;
+                }
+
+                // pack the pixels in the layout of the visual
+                let bytes_per_pixel = ((*ximage).bits_per_pixel / 8) as usize;
+                let stride = (*ximage).bytes_per_line as usize;
+                let msb_first = (*ximage).byte_order == xlib::MSBFirst;
+                let mut data = vec![0u8; stride * height as usize];
+                for (x, y, pixel) in image.enumerate_pixels() {
+                    let (r, g, b, _) = pixel.channels4();
+                    let value = self.red.pack(r) | self.green.pack(g) | self.blue.pack(b);
+                    let offset = y as usize * stride + x as usize * bytes_per_pixel;
+                    let bytes = &mut data[offset..offset + bytes_per_pixel];
+                    for (i, byte) in bytes.iter_mut().enumerate() {
+                        let shift = match msb_first {
+                            true => bytes_per_pixel - 1 - i,
+                            false => i,
+                        };
+                        *byte = (value >> (shift * 8)) as u8;
+                    }
+                }
+
+                (*ximage).data = data.as_mut_ptr() as *mut c_char;
+                (self.xlib.XPutImage)(
+                    self.display,
+                    self.window,
+                    self.gc,
+                    ximage,
+                    0,
+                    0,
+                    0,
+                    0,
+                    width,
+                    height,
+                );
+                // the pixel data is still owned by us, so only the image struct is freed
+                (*ximage).data = ptr::null_mut();
+                (self.xlib.XFree)(ximage as *mut c_void);
+                (self.xlib.XFlush)(self.display);
+            }
+        }
+    }
+
+    impl Drop for Presenter {
+        fn drop(&mut self) {
+            unsafe {
+                (self.xlib.XFreeGC)(self.display, self.gc);
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+use windows::Presenter;
+
+#[cfg(windows)]
+mod windows {
+    use image::{Pixel, RgbaImage};
+    use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+    use std::mem;
+    use winapi::ctypes::c_void;
+    use winapi::shared::windef::HWND;
+    use winapi::um::wingdi::{
+        SetDIBitsToDevice, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+    };
+    use winapi::um::winuser::{GetDC, ReleaseDC};
+    use winit::window::Window;
+
+    /// Copies images to a window with `SetDIBitsToDevice`
+    pub struct Presenter {
+        hwnd: HWND,
+    }
+
+    impl Presenter {
+        pub fn new(window: &Window) -> Option<Self> {
+            match window.raw_window_handle() {
+                RawWindowHandle::Windows(handle) => Some(Presenter {
+                    hwnd: handle.hwnd as HWND,
+                }),
+                _ => None,
+            }
+        }
+
+        pub fn present(&mut self, image: &RgbaImage) {
+            let (width, height) = image.dimensions();
+            // 32 bit bitmaps are stored as little endian xrgb
+            let data: Vec<u32> = image
+                .pixels()
+                .map(|pixel| {
+                    let (r, g, b, _) = pixel.channels4();
+                    (r as u32) << 16 | (g as u32) << 8 | b as u32
+                })
+                .collect();
+
+            unsafe {
+                let mut info: BITMAPINFO = mem::zeroed();
+                info.bmiHeader.biSize = mem::size_of::<BITMAPINFOHEADER>() as u32;
+                info.bmiHeader.biWidth = width as i32;
+                // a negative height stores the rows from top to bottom
+                info.bmiHeader.biHeight = -(height as i32);
+                info.bmiHeader.biPlanes = 1;
+                info.bmiHeader.biBitCount = 32;
+                info.bmiHeader.biCompression = BI_RGB;
+
+                let hdc = GetDC(self.hwnd);
+                if hdc.is_null() {
+                    return;
+                }
+                SetDIBitsToDevice(
+                    hdc,
+                    0,
+                    0,
+                    width,
+                    height,
+                    0,
+                    0,
+                    0,
+                    height,
+                    data.as_ptr() as *const c_void,
+                    &info,
+                    DIB_RGB_COLORS,
+                );
+                ReleaseDC(self.hwnd, hdc);
+            }
+        }
+    }
+}
+
+#[cfg(not(any(windows, all(unix, not(target_os = "macos")))))]
+struct Presenter;
+
+#[cfg(not(any(windows, all(unix, not(target_os = "macos")))))]
+impl Presenter {
+    fn new(_window: &Window) -> Option<Self> {
+        None
+    }
+
+    fn present(&mut self, _image: &RgbaImage) {}
+}
@@ -0,0 +1,323 @@
+use crate::config::{config_dir, read_config_file, ConfigError};
+use conrod_core::{Color, FontSize, Scalar};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The theme file as written by the user
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct RawTheme {
+    background: Option<String>,
+    selected: Option<String>,
+    text: Option<String>,
+    highlight: Option<String>,
+    subtitle: Option<String>,
+    mark: Option<String>,
+    border: Option<String>,
+    border_width: Option<Scalar>,
+    padding: Option<Scalar>,
+    row_height: Option<u32>,
+    font_size: Option<FontSize>,
+    subtitle_font_size: Option<FontSize>,
+    font: Option<String>,
+    fallback_fonts: Option<Vec<String>>,
+}
+
+/// Colors and sizes of the menu, read from `$XDG_CONFIG_HOME/rufi/theme.toml`
+///
+/// ```toml
+/// background = "#282828"
+/// selected = "#504945"
+/// text = "#ebdbb2"
+/// highlight = "#83a598"
+/// border = "#ebdbb2"
+/// border-width = 2
+/// padding = 4
+/// row-height = 24
+/// font-size = 16
+/// font = "Fira Sans"
+/// fallback-fonts = ["Noto Sans CJK JP", "Noto Emoji"]
+/// ```
+///
+/// Anything not set in the theme keeps the value from the builder.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThemeConfig {
+    pub background: Option<Color>,
+    /// Background of the selected row
+    pub selected: Option<Color>,
+    pub text: Option<Color>,
+    /// Color for the characters that matched the search
+    pub highlight: Option<Color>,
+    pub subtitle: Option<Color>,
+    /// Color for marked rows in multi select mode
+    pub mark: Option<Color>,
+    pub border: Option<Color>,
+    pub border_width: Option<Scalar>,
+    /// Space between the border and the content
+    pub padding: Option<Scalar>,
+    pub row_height: Option<u32>,
+    pub font_size: Option<FontSize>,
+    pub subtitle_font_size: Option<FontSize>,
+    /// Font family for the text
+    pub font: Option<String>,
+    /// Font families for the characters missing from the text font, replacing the default
+    /// fallback fonts
+    pub fallback_fonts: Option<Vec<String>>,
+}
+
+impl ThemeConfig {
+    /// Location of the theme file
+    pub fn path() -> Option<PathBuf> {
+        Some(config_dir()?.join("theme.toml"))
+    }
+
+    /// Load the theme file of the user, a missing theme file gives an empty theme
+    pub fn load() -> Result<Self, ConfigError> {
+        match ThemeConfig::path() {
+            Some(path) if path.exists() => ThemeConfig::from_file(&path),
+            _ => Ok(ThemeConfig::default()),
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        read_config_file(path)?.parse()
+    }
+}
+
+impl FromStr for ThemeConfig {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let raw: RawTheme = toml::from_str(s).map_err(ConfigError::Parse)?;
+        let color = |color: Option<String>| {
+            color
+                .map(|color| parse_color(&color).ok_or(ConfigError::Color(color)))
+                .transpose()
+        };
+
+        Ok(ThemeConfig {
+            background: color(raw.background)?,
+            selected: color(raw.selected)?,
+            text: color(raw.text)?,
+            highlight: color(raw.highlight)?,
+            subtitle: color(raw.subtitle)?,
+            mark: color(raw.mark)?,
+            border: color(raw.border)?,
+            border_width: raw.border_width,
+            padding: raw.padding,
+            row_height: raw.row_height,
+            font_size: raw.font_size,
+            subtitle_font_size: raw.subtitle_font_size,
+            font: raw.font,
+            fallback_fonts: raw.fallback_fonts,
+        })
+    }
+}
+
+/// Parse a color written as `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa` or as a name like `red`
+pub(crate) fn parse_color(color: &str) -> Option<Color> {
+    let hex = match color.strip_prefix('#') {
+        Some(hex) => hex,
+        None => return named_color(color),
+    };
+    if !hex.is_ascii() {
+        return None;
+    }
+
+    // the short forms repeat every digit, so `#f00` is `#ff0000`
+    let digits = match hex.len() {
+        3 | 4 => 1,
+        6 | 8 => 2,
+        _ => return None,
+    };
+    let channel = |index: usize| {
+        let value = u8::from_str_radix(&hex[index * digits..(index + 1) * digits], 16).ok()?;
+        Some(match digits {
+            1 => value * 17,
+            _ => value,
+        })
+    };
+    let a = match hex.len() / digits {
+        4 => channel(3)?,
+        _ => 255,
+    };
+    Some(conrod_core::color::rgba_bytes(
+        channel(0)?,
+        channel(1)?,
+        channel(2)?,
+        a as f32 / 255.0,
+    ))
+}
+
+/// Look up a color by name, ignoring case and spaces like Pango does
+fn named_color(name: &str) -> Option<Color> {
+    let name: String = name
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let index = NAMED_COLORS
+        .binary_search_by(|(color, _)| color.cmp(&name.as_str()))
+        .ok()?;
+    let rgb = NAMED_COLORS[index].1;
+    Some(conrod_core::color::rgb_bytes(
+        (rgb >> 16) as u8,
+        (rgb >> 8) as u8,
+        rgb as u8,
+    ))
+}
+
+/// The CSS color names, sorted for the lookup
+///
+/// Where CSS and X11 disagree, like for `green` and `gray`, the X11 color is used as Pango does.
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0xbebebe),
+    ("green", 0x00ff00),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0xbebebe),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0xb03060),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0xa020f0),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
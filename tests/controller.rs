@@ -0,0 +1,273 @@
+use rufi::{
+    Action, AppEvent, Config, Input, KeyCombo, MenuAppBuilder, MenuController, MenuItem, Outcome,
+};
+use std::borrow::Cow;
+use winit::event::{ModifiersState, VirtualKeyCode};
+
+fn controller(builder: MenuAppBuilder, items: &[&str]) -> MenuController<String> {
+    let mut controller = builder.build_controller().unwrap();
+    controller.set_items(items.iter().map(|item| item.to_string()).collect());
+    controller
+}
+
+fn key(key: VirtualKeyCode) -> Input {
+    Input::Key(key, ModifiersState::empty())
+}
+
+fn results(controller: &MenuController<String>) -> Vec<&str> {
+    controller.state().results().map(String::as_str).collect()
+}
+
+#[test]
+fn test_filter_on_query() {
+    let mut controller = controller(MenuAppBuilder::new(), &["firefox", "files", "terminal"]);
+    assert_eq!(results(&controller), vec!["firefox", "files", "terminal"]);
+
+    let event = controller.handle_input(Input::Query("fi".to_string()));
+    assert_eq!(event, AppEvent::Search("fi".to_string()));
+    assert_eq!(controller.state().search(), "fi");
+    assert_eq!(results(&controller), vec!["files", "firefox"]);
+}
+
+#[test]
+fn test_select() {
+    let mut controller = controller(MenuAppBuilder::new(), &["one", "two", "three"]);
+    assert_eq!(
+        controller.handle_input(key(VirtualKeyCode::Down)),
+        AppEvent::Continue
+    );
+    assert_eq!(controller.state().selected(), 1);
+
+    assert_eq!(
+        controller.handle_input(key(VirtualKeyCode::Return)),
+        AppEvent::Exit
+    );
+    assert_eq!(
+        controller.into_outcome(),
+        Outcome::Selected("two".to_string(), 1)
+    );
+}
+
+#[test]
+fn test_custom_query_without_matches() {
+    let mut controller = controller(MenuAppBuilder::new(), &["one", "two"]);
+    controller.handle_input(Input::Query("xyz".to_string()));
+    controller.handle_input(key(VirtualKeyCode::Return));
+    assert_eq!(
+        controller.into_outcome(),
+        Outcome::Custom("xyz".to_string())
+    );
+}
+
+#[test]
+fn test_cancel() {
+    let mut controller = controller(MenuAppBuilder::new(), &["one"]);
+    assert_eq!(
+        controller.handle_input(key(VirtualKeyCode::Escape)),
+        AppEvent::Exit
+    );
+    assert_eq!(controller.into_outcome(), Outcome::Cancelled);
+
+    let mut controller = self::controller(MenuAppBuilder::new(), &["one"]);
+    controller.handle_input(Input::FocusLost);
+    assert_eq!(controller.into_outcome(), Outcome::FocusLost);
+}
+
+#[test]
+fn test_emacs_and_vi_movement() {
+    let mut controller = controller(MenuAppBuilder::new(), &["one", "two", "three"]);
+    controller.handle_input(Input::Key(VirtualKeyCode::N, ModifiersState::CTRL));
+    controller.handle_input(Input::Key(VirtualKeyCode::J, ModifiersState::CTRL));
+    assert_eq!(controller.state().selected(), 2);
+    controller.handle_input(Input::Key(VirtualKeyCode::P, ModifiersState::CTRL));
+    assert_eq!(controller.state().selected(), 1);
+    controller.handle_input(Input::Key(VirtualKeyCode::K, ModifiersState::CTRL));
+    assert_eq!(controller.state().selected(), 0);
+}
+
+#[test]
+fn test_wrap_around() {
+    let mut controller = controller(MenuAppBuilder::new(), &["one", "two", "three"]);
+    controller.handle_input(key(VirtualKeyCode::Up));
+    assert_eq!(controller.state().selected(), 0);
+
+    let mut controller = self::controller(
+        MenuAppBuilder::new().wrap_around(true),
+        &["one", "two", "three"],
+    );
+    controller.handle_input(key(VirtualKeyCode::Up));
+    assert_eq!(controller.state().selected(), 2);
+    controller.handle_input(key(VirtualKeyCode::Down));
+    assert_eq!(controller.state().selected(), 0);
+}
+
+#[test]
+fn test_page_and_scroll() {
+    let items: Vec<String> = (0..20).map(|i| format!("item {}", i)).collect();
+    let items: Vec<&str> = items.iter().map(String::as_str).collect();
+    let mut controller = controller(MenuAppBuilder::new().max_rows(5), &items);
+
+    controller.handle_input(key(VirtualKeyCode::PageDown));
+    assert_eq!(controller.state().selected(), 5);
+    assert_eq!(controller.state().scroll(), 1);
+
+    controller.handle_input(key(VirtualKeyCode::End));
+    assert_eq!(controller.state().selected(), 19);
+    assert_eq!(controller.state().scroll(), 15);
+
+    controller.handle_input(key(VirtualKeyCode::PageUp));
+    assert_eq!(controller.state().selected(), 14);
+    assert_eq!(controller.state().scroll(), 14);
+
+    controller.handle_input(key(VirtualKeyCode::Home));
+    assert_eq!(controller.state().selected(), 0);
+    assert_eq!(controller.state().scroll(), 0);
+}
+
+#[test]
+fn test_complete() {
+    let mut controller = controller(
+        MenuAppBuilder::new(),
+        &["/usr/bin/firefox", "/usr/bin/files", "/etc/fstab"],
+    );
+    controller.handle_input(Input::Query("/usr".to_string()));
+
+    // first to the common prefix of the results
+    let event = controller.handle_input(key(VirtualKeyCode::Tab));
+    assert_eq!(event, AppEvent::Search("/usr/bin/fi".to_string()));
+    assert_eq!(controller.state().search(), "/usr/bin/fi");
+
+    // then to the selected item
+    controller.handle_input(key(VirtualKeyCode::Tab));
+    assert_eq!(controller.state().search(), "/usr/bin/files");
+
+    assert_eq!(
+        controller.handle_input(key(VirtualKeyCode::Tab)),
+        AppEvent::Continue
+    );
+}
+
+#[test]
+fn test_custom_key() {
+    let mut controller = controller(MenuAppBuilder::new(), &["one", "two"]);
+    controller.handle_input(Input::Key(VirtualKeyCode::Key2, ModifiersState::ALT));
+    assert_eq!(
+        controller.into_outcome(),
+        Outcome::CustomKey(2, Some("one".to_string()))
+    );
+}
+
+#[test]
+fn test_multi_select() {
+    let mut controller = controller(
+        MenuAppBuilder::new().multi_select(true),
+        &["one", "two", "three"],
+    );
+    let toggle = Input::Key(VirtualKeyCode::Space, ModifiersState::CTRL);
+    controller.handle_input(toggle.clone());
+    controller.handle_input(key(VirtualKeyCode::Down));
+    controller.handle_input(toggle);
+    assert!(controller.state().is_marked(0));
+    assert!(!controller.state().is_marked(1));
+    assert!(controller.state().is_marked(2));
+
+    controller.handle_input(key(VirtualKeyCode::Return));
+    assert_eq!(
+        controller.into_outcome(),
+        Outcome::Marked(vec![("one".to_string(), 0), ("three".to_string(), 2)])
+    );
+}
+
+#[test]
+fn test_custom_keymap() {
+    let config: Config = r#"
+        [keys]
+        "ctrl+d" = "down"
+        "Down" = "none"
+    "#
+    .parse()
+    .unwrap();
+    let builder = MenuAppBuilder::new()
+        .config(&config)
+        .bind("alt+x".parse().unwrap(), Action::Cancel)
+        .bind(KeyCombo::from(VirtualKeyCode::F1), Action::Custom(1));
+    let mut controller = controller(builder, &["one", "two", "three"]);
+
+    controller.handle_input(key(VirtualKeyCode::Down));
+    assert_eq!(controller.state().selected(), 0);
+    controller.handle_input(Input::Key(VirtualKeyCode::D, ModifiersState::CTRL));
+    assert_eq!(controller.state().selected(), 1);
+
+    assert_eq!(
+        controller.handle_input(Input::Key(VirtualKeyCode::X, ModifiersState::ALT)),
+        AppEvent::Exit
+    );
+}
+
+#[test]
+fn test_invalid_keymap() {
+    assert!("[keys]\n\"ctrl+nope\" = \"down\""
+        .parse::<Config>()
+        .is_err());
+    assert!("[keys]\n\"ctrl+n\" = \"sideways\""
+        .parse::<Config>()
+        .is_err());
+}
+
+#[test]
+fn test_input_after_exit_is_ignored() {
+    let mut controller = controller(MenuAppBuilder::new(), &["one", "two"]);
+    controller.handle_input(key(VirtualKeyCode::Down));
+    assert_eq!(
+        controller.handle_input(key(VirtualKeyCode::Return)),
+        AppEvent::Exit
+    );
+    assert_eq!(controller.handle_input(Input::FocusLost), AppEvent::Exit);
+    assert_eq!(
+        controller.handle_input(key(VirtualKeyCode::Escape)),
+        AppEvent::Exit
+    );
+    assert_eq!(
+        controller.handle_input(key(VirtualKeyCode::Return)),
+        AppEvent::Exit
+    );
+    assert_eq!(
+        controller.into_outcome(),
+        Outcome::Selected("two".to_string(), 1)
+    );
+}
+
+/// An item matched on another text than its title
+struct Command {
+    name: &'static str,
+    path: &'static str,
+}
+
+impl MenuItem for Command {
+    fn title(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self.name)
+    }
+
+    fn search_key(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self.path)
+    }
+}
+
+#[test]
+fn test_complete_search_key() {
+    let mut controller: MenuController<Command> = MenuAppBuilder::new().build_controller().unwrap();
+    controller.set_items(vec![
+        Command {
+            name: "Firefox",
+            path: "/usr/bin/firefox",
+        },
+        Command {
+            name: "Files",
+            path: "/usr/bin/nautilus",
+        },
+    ]);
+    controller.handle_input(Input::Query("/usr".to_string()));
+
+    controller.handle_input(key(VirtualKeyCode::Tab));
+    assert_eq!(controller.state().search(), "/usr/bin/");
+}
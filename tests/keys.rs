@@ -0,0 +1,74 @@
+use rufi::{Config, KeyCombo, KeyError};
+use winit::event::{ModifiersState, VirtualKeyCode};
+
+fn combo(s: &str) -> Result<KeyCombo, KeyError> {
+    s.parse()
+}
+
+#[test]
+fn test_parse_key() {
+    assert_eq!(combo("n"), Ok(KeyCombo::from(VirtualKeyCode::N)));
+    assert_eq!(combo("N"), Ok(KeyCombo::from(VirtualKeyCode::N)));
+    assert_eq!(combo("5"), Ok(KeyCombo::from(VirtualKeyCode::Key5)));
+    assert_eq!(
+        combo("Page_Down"),
+        Ok(KeyCombo::from(VirtualKeyCode::PageDown))
+    );
+    assert_eq!(combo("esc"), Ok(KeyCombo::from(VirtualKeyCode::Escape)));
+}
+
+#[test]
+fn test_parse_named_key() {
+    assert_eq!(combo("F5"), Ok(KeyCombo::from(VirtualKeyCode::F5)));
+    assert_eq!(combo("f12"), Ok(KeyCombo::from(VirtualKeyCode::F12)));
+    assert_eq!(combo("comma"), Ok(KeyCombo::from(VirtualKeyCode::Comma)));
+    assert_eq!(
+        combo("ctrl+bracketleft"),
+        Ok(KeyCombo::new(
+            VirtualKeyCode::LBracket,
+            ModifiersState::CTRL
+        ))
+    );
+    assert_eq!(
+        combo("KP_Enter"),
+        Ok(KeyCombo::from(VirtualKeyCode::Return))
+    );
+    assert_eq!(combo("KP_5"), Ok(KeyCombo::from(VirtualKeyCode::Numpad5)));
+    assert!(combo("F0").is_err());
+    assert!(combo("F25").is_err());
+}
+
+#[test]
+fn test_parse_modifiers() {
+    assert_eq!(
+        combo("ctrl+shift+n"),
+        Ok(KeyCombo::new(
+            VirtualKeyCode::N,
+            ModifiersState::CTRL | ModifiersState::SHIFT
+        ))
+    );
+    assert_eq!(combo("Control + N"), combo("ctrl+n"));
+    assert_eq!(
+        combo("mod4+Return"),
+        Ok(KeyCombo::new(VirtualKeyCode::Return, ModifiersState::LOGO))
+    );
+}
+
+#[test]
+fn test_parse_invalid_key() {
+    assert!(combo("").is_err());
+    assert!(combo("ctrl+").is_err());
+    assert!(combo("hyper+n").is_err());
+    assert!(combo("nope").is_err());
+}
+
+#[test]
+fn test_duplicate_binding() {
+    let config = r#"
+        [keys]
+        "ctrl+n" = "down"
+        "control+N" = "up"
+    "#
+    .parse::<Config>();
+    assert!(config.is_err());
+}
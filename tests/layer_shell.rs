@@ -0,0 +1,97 @@
+#![cfg(all(feature = "layer-shell", unix, not(target_os = "macos")))]
+
+use rufi::{MenuApp, Outcome};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A sway instance without any outputs or input devices of its own
+struct HeadlessSway {
+    process: Child,
+    runtime_dir: PathBuf,
+}
+
+impl HeadlessSway {
+    fn start() -> Self {
+        let runtime_dir = std::env::temp_dir().join(format!("rufi-sway-{}", std::process::id()));
+        fs::create_dir_all(&runtime_dir).unwrap();
+        fs::set_permissions(&runtime_dir, fs::Permissions::from_mode(0o700)).unwrap();
+        let config = runtime_dir.join("config");
+        fs::write(&config, "").unwrap();
+
+        let process = Command::new("sway")
+            .arg("--config")
+            .arg(&config)
+            .env("XDG_RUNTIME_DIR", &runtime_dir)
+            .env("WLR_BACKENDS", "headless")
+            .env("WLR_LIBINPUT_NO_DEVICES", "1")
+            .env_remove("DISPLAY")
+            .env_remove("WAYLAND_DISPLAY")
+            .spawn()
+            .expect("sway needs to be installed");
+        HeadlessSway {
+            process,
+            runtime_dir,
+        }
+    }
+
+    /// The name of the socket, once sway is listening on it
+    fn wait_for_socket(&self) -> String {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(10) {
+            if let Some(socket) = find_socket(&self.runtime_dir) {
+                return socket;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        panic!("sway didn't start");
+    }
+}
+
+impl Drop for HeadlessSway {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+        let _ = fs::remove_dir_all(&self.runtime_dir);
+    }
+}
+
+fn find_socket(runtime_dir: &Path) -> Option<String> {
+    fs::read_dir(runtime_dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .find(|name| name.starts_with("wayland-") && !name.ends_with(".lock"))
+}
+
+/// Shows the menu on a headless sway and selects an item with keys typed by `wtype`
+///
+/// Run with `cargo test --features layer-shell -- --ignored`.
+#[test]
+#[ignore]
+fn test_layer_shell_select() {
+    let sway = HeadlessSway::start();
+    let socket = sway.wait_for_socket();
+    std::env::set_var("XDG_RUNTIME_DIR", &sway.runtime_dir);
+    std::env::set_var("WAYLAND_DISPLAY", &socket);
+    std::env::remove_var("DISPLAY");
+
+    // the menu grabs the keyboard once it's shown
+    let typing = thread::spawn(|| {
+        thread::sleep(Duration::from_secs(1));
+        Command::new("wtype")
+            .args(&["-k", "Down", "-k", "Return"])
+            .status()
+            .expect("wtype needs to be installed")
+    });
+
+    let mut app: MenuApp<String> = MenuApp::new(400, "rufi").unwrap();
+    app.set_items(vec!["one".to_string(), "two".to_string()]);
+    let outcome = app.run().unwrap();
+
+    assert!(typing.join().unwrap().success());
+    assert_eq!(outcome, Outcome::Selected("two".to_string(), 1));
+}
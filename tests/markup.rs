@@ -0,0 +1,75 @@
+use conrod_core::color;
+use rufi::{parse_markup, strip_markup, MarkupItem, MenuItem, Span};
+
+fn span(text: &str, bold: bool, italic: bool) -> Span {
+    Span {
+        text: text.to_string(),
+        bold,
+        italic,
+        color: None,
+    }
+}
+
+#[test]
+fn test_parse_markup() {
+    assert_eq!(
+        parse_markup("a <b>bold <i>and italic</i></b> text").unwrap(),
+        vec![
+            span("a ", false, false),
+            span("bold ", true, false),
+            span("and italic", true, true),
+            span(" text", false, false),
+        ]
+    );
+
+    let spans = parse_markup(r##"<span foreground="#ff0000" weight="bold">red</span>"##).unwrap();
+    assert_eq!(spans.len(), 1);
+    assert!(spans[0].bold);
+    assert_eq!(spans[0].color, Some(color::rgb_bytes(255, 0, 0)));
+}
+
+#[test]
+fn test_markup_colors() {
+    let color_of = |markup: &str| parse_markup(markup).unwrap()[0].color;
+    assert_eq!(
+        color_of(r#"<span foreground="red">red</span>"#),
+        Some(color::rgb_bytes(255, 0, 0))
+    );
+    assert_eq!(
+        color_of(r#"<span color="Dark Orange">orange</span>"#),
+        Some(color::rgb_bytes(255, 140, 0))
+    );
+    assert_eq!(
+        color_of(r##"<span fgcolor="#0f0">green</span>"##),
+        Some(color::rgb_bytes(0, 255, 0))
+    );
+    assert_eq!(
+        parse_markup(r#"<span foreground="not a color">text</span>"#),
+        None
+    );
+}
+
+#[test]
+fn test_markup_entities() {
+    assert_eq!(
+        strip_markup("fish &amp; chips &lt;3 &#x2764;"),
+        "fish & chips <3 \u{2764}"
+    );
+}
+
+#[test]
+fn test_invalid_markup() {
+    assert_eq!(parse_markup("<b>unclosed"), None);
+    assert_eq!(parse_markup("<b>crossed <i>tags</b></i>"), None);
+    assert_eq!(parse_markup("<blink>unknown</blink>"), None);
+    assert_eq!(parse_markup("fish & chips"), None);
+    // invalid markup is shown as is
+    assert_eq!(strip_markup("fish & chips"), "fish & chips");
+}
+
+#[test]
+fn test_markup_item() {
+    let item = MarkupItem::new("<b>Fire</b>fox");
+    assert_eq!(item.title(), "Firefox");
+    assert_eq!(item.into_markup(), "<b>Fire</b>fox");
+}
@@ -0,0 +1,242 @@
+use rufi::{Anchor, Config, EdgeAnchors, MonitorSelection, Offset, Placement};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+#[test]
+fn test_parse_anchor() {
+    assert_eq!("top".parse(), Ok(Anchor::Top));
+    assert_eq!("Bottom-Right".parse(), Ok(Anchor::BottomRight));
+    assert_eq!("top_left".parse(), Ok(Anchor::TopLeft));
+    assert!("middle".parse::<Anchor>().is_err());
+}
+
+#[test]
+fn test_parse_offset() {
+    assert_eq!("10".parse(), Ok(Offset::Pixels(10)));
+    assert_eq!("-10px".parse(), Ok(Offset::Pixels(-10)));
+    assert_eq!("5%".parse(), Ok(Offset::Percent(5.0)));
+    assert!("ten".parse::<Offset>().is_err());
+}
+
+#[test]
+fn test_placement_config() {
+    let config: Config = r#"
+        [placement]
+        anchor = "bottom"
+        offset-x = 20
+        offset-y = "-5%"
+        full-width = true
+    "#
+    .parse()
+    .unwrap();
+    assert_eq!(config.anchor, Some(Anchor::Bottom));
+    assert_eq!(config.offset_x, Some(Offset::Pixels(20)));
+    assert_eq!(config.offset_y, Some(Offset::Percent(-5.0)));
+    assert_eq!(config.full_width, Some(true));
+    assert_eq!(config.at_cursor, None);
+
+    assert!("[placement]\nanchor = \"middle\""
+        .parse::<Config>()
+        .is_err());
+}
+
+#[test]
+fn test_monitor_config() {
+    let monitor = |config: &str| config.parse::<Config>().unwrap().monitor;
+    assert_eq!(
+        monitor("[placement]\nmonitor = \"cursor\""),
+        Some(MonitorSelection::Cursor)
+    );
+    assert_eq!(
+        monitor("[placement]\nmonitor = 1"),
+        Some(MonitorSelection::Index(1))
+    );
+    assert_eq!(
+        monitor("[placement]\nmonitor = \"DP-1\""),
+        Some(MonitorSelection::Name("DP-1".to_string()))
+    );
+    assert_eq!(monitor(""), None);
+
+    // negative indices like in rofi
+    assert_eq!(
+        monitor("[placement]\nmonitor = -1"),
+        Some(MonitorSelection::Focused)
+    );
+    assert_eq!(
+        monitor("[placement]\nmonitor = -3"),
+        Some(MonitorSelection::Cursor)
+    );
+    assert!("[placement]\nmonitor = -7".parse::<Config>().is_err());
+}
+
+#[test]
+fn test_parse_monitor() {
+    let monitor = |s: &str| s.parse::<MonitorSelection>().unwrap();
+    assert_eq!(monitor("2"), MonitorSelection::Index(2));
+    assert_eq!(monitor("-1"), MonitorSelection::Focused);
+    assert_eq!(monitor("-4"), MonitorSelection::Focused);
+    assert_eq!(monitor("-5"), MonitorSelection::Cursor);
+    assert_eq!(monitor("primary"), MonitorSelection::Primary);
+    assert_eq!(
+        monitor("HDMI-A-1"),
+        MonitorSelection::Name("HDMI-A-1".to_string())
+    );
+}
+
+const MONITOR: PhysicalSize<u32> = PhysicalSize {
+    width: 1920,
+    height: 1080,
+};
+const WINDOW: PhysicalSize<u32> = PhysicalSize {
+    width: 600,
+    height: 300,
+};
+
+fn position(placement: Placement, scale_factor: f64, cursor: Option<(i32, i32)>) -> (i32, i32) {
+    let position = placement.position(
+        PhysicalPosition::new(1920, 0),
+        MONITOR,
+        scale_factor,
+        WINDOW,
+        cursor.map(|(x, y)| PhysicalPosition::new(x, y)),
+    );
+    (position.x - 1920, position.y)
+}
+
+#[test]
+fn test_position_anchors() {
+    // centered horizontally, a third of the free space above the window
+    assert_eq!(position(Placement::default(), 1.0, None), (660, 260));
+    assert_eq!(
+        position(Placement::anchored(Anchor::TopLeft), 1.0, None),
+        (0, 0)
+    );
+    assert_eq!(
+        position(Placement::anchored(Anchor::BottomRight), 1.0, None),
+        (1320, 780)
+    );
+}
+
+#[test]
+fn test_position_offsets() {
+    // pixel offsets are logical and scaled with the monitor
+    let placement =
+        Placement::anchored(Anchor::TopRight).offset(Offset::Pixels(-10), Offset::Pixels(10));
+    assert_eq!(position(placement, 2.0, None), (1300, 20));
+
+    // percentages are of the monitor size
+    let placement =
+        Placement::anchored(Anchor::Bottom).offset(Offset::Pixels(0), Offset::Percent(-5.0));
+    assert_eq!(position(placement, 2.0, None), (660, 726));
+}
+
+#[test]
+fn test_position_at_cursor() {
+    let placement = Placement::default().at_cursor(true);
+    assert_eq!(position(placement, 1.0, Some((2020, 50))), (100, 50));
+    // the window is kept on the monitor when the cursor is near the edge
+    assert_eq!(
+        position(placement, 1.0, Some((1920 + 1900, 1070))),
+        (1320, 780)
+    );
+    let placement = placement.offset(Offset::Pixels(-20), Offset::Pixels(-20));
+    assert_eq!(position(placement, 1.0, Some((1930, 10))), (0, 0));
+    // without a cursor position the anchors are used
+    assert_eq!(position(placement, 1.0, None), (640, 240));
+    // and the cursor is ignored when not placing at the cursor
+    assert_eq!(
+        position(Placement::default(), 1.0, Some((2020, 50))),
+        (660, 260)
+    );
+}
+
+#[test]
+fn test_full_width() {
+    assert_eq!(Placement::default().window_width(MONITOR), None);
+    assert_eq!(
+        Placement::default().full_width(true).window_width(MONITOR),
+        Some(1920)
+    );
+}
+
+const OUTPUT: Option<(u32, u32)> = Some((1920, 1080));
+
+fn anchors(top: bool, right: bool, bottom: bool, left: bool, margin: [i32; 4]) -> EdgeAnchors {
+    EdgeAnchors {
+        top,
+        right,
+        bottom,
+        left,
+        margin,
+        width: 600,
+        height: 300,
+    }
+}
+
+#[test]
+fn test_edge_anchors() {
+    // centering is left to the compositor, other positions are a margin from the top or left
+    assert_eq!(
+        Placement::default().edge_anchors((600, 300), OUTPUT),
+        anchors(true, false, false, false, [260, 0, 0, 0])
+    );
+    assert_eq!(
+        Placement::anchored(Anchor::Center).edge_anchors((600, 300), OUTPUT),
+        anchors(false, false, false, false, [0, 0, 0, 0])
+    );
+    assert_eq!(
+        Placement::anchored(Anchor::TopLeft).edge_anchors((600, 300), OUTPUT),
+        anchors(true, false, false, true, [0, 0, 0, 0])
+    );
+    assert_eq!(
+        Placement::anchored(Anchor::BottomRight).edge_anchors((600, 300), OUTPUT),
+        anchors(false, true, true, false, [0, 0, 0, 0])
+    );
+    // without the output size only the edges and the center are known
+    assert_eq!(
+        Placement::default().edge_anchors((600, 300), None),
+        anchors(false, false, false, false, [0, 0, 0, 0])
+    );
+}
+
+#[test]
+fn test_edge_anchor_margins() {
+    // offsets move away from the anchored edge, or towards it when negative
+    let placement =
+        Placement::anchored(Anchor::BottomRight).offset(Offset::Pixels(-10), Offset::Pixels(-20));
+    assert_eq!(
+        placement.edge_anchors((600, 300), OUTPUT),
+        anchors(false, true, true, false, [0, 10, 20, 0])
+    );
+    let placement = Placement::anchored(Anchor::Left).offset(Offset::Pixels(10), Offset::Pixels(0));
+    assert_eq!(
+        placement.edge_anchors((600, 300), OUTPUT),
+        anchors(false, false, false, true, [0, 0, 0, 10])
+    );
+    // a centered surface with an offset needs a margin from the edge
+    let placement =
+        Placement::anchored(Anchor::Center).offset(Offset::Pixels(0), Offset::Pixels(10));
+    assert_eq!(
+        placement.edge_anchors((600, 300), OUTPUT),
+        anchors(true, false, false, false, [400, 0, 0, 0])
+    );
+}
+
+#[test]
+fn test_edge_anchors_full_width() {
+    let placement = Placement::anchored(Anchor::Top).full_width(true);
+    assert_eq!(
+        placement.edge_anchors((600, 300), OUTPUT),
+        EdgeAnchors {
+            width: 0,
+            ..anchors(true, true, false, true, [0, 0, 0, 0])
+        }
+    );
+    let placement = placement.offset(Offset::Percent(5.0), Offset::Pixels(0));
+    assert_eq!(
+        placement.edge_anchors((600, 300), OUTPUT),
+        EdgeAnchors {
+            width: 0,
+            ..anchors(true, true, false, true, [0, -96, 0, 96])
+        }
+    );
+}
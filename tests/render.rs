@@ -0,0 +1,146 @@
+use rufi::{BuildError, Icon, MarkupItem, MenuApp, MenuAppBuilder, MenuItem, ThemeConfig};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+fn render(builder: MenuAppBuilder, items: &[&str]) -> image::RgbaImage {
+    let mut app: MenuApp<String> = builder.build().unwrap();
+    app.set_items(items.iter().map(|item| item.to_string()).collect());
+    app.render_to_image()
+}
+
+#[test]
+fn test_render_size() {
+    let image = render(MenuAppBuilder::new().width(400), &["one", "two", "three"]);
+    // the search input and three rows of 18 pixels, with a margin of 2 around them
+    assert_eq!(image.dimensions(), (400, 18 + 3 * 18 + 4));
+
+    let image = render(
+        MenuAppBuilder::new().width(400).max_rows(2),
+        &["one", "two", "three"],
+    );
+    assert_eq!(image.dimensions(), (400, 18 + 2 * 18 + 4));
+}
+
+#[test]
+fn test_render_selected_row() {
+    let image = render(MenuAppBuilder::new(), &["one", "two"]);
+    // the right side of the rows, clear of any text
+    let selected = image.get_pixel(590, 20 + 9);
+    let other = image.get_pixel(590, 20 + 18 + 9);
+    assert_ne!(selected, other);
+}
+
+#[test]
+fn test_render_theme() {
+    let theme: ThemeConfig = r##"
+        background = "#102030"
+        border = "#ff0000"
+        border-width = 3
+        padding = 2
+        row-height = 24
+    "##
+    .parse()
+    .unwrap();
+    let image = render(
+        MenuAppBuilder::new().width(400).theme_config(&theme),
+        &["one", "two", "three"],
+    );
+    // the search input and three rows of 24 pixels, with a border of 3 and a padding of 2
+    assert_eq!(image.dimensions(), (400, 18 + 3 * 24 + 10));
+    // the last row isn't selected, so it shows the background color
+    let pixel = image.get_pixel(390, 18 + 2 * 24 + 5 + 12);
+    assert_eq!(&pixel.0[..3], &[0x10, 0x20, 0x30]);
+    let pixel = image.get_pixel(1, 40);
+    assert_eq!(&pixel.0[..3], &[0xff, 0, 0]);
+}
+
+#[test]
+fn test_invalid_theme() {
+    assert!("background = \"102030\"".parse::<ThemeConfig>().is_err());
+    assert!("background = \"#10203\"".parse::<ThemeConfig>().is_err());
+    assert!("background = \"#1020zz\"".parse::<ThemeConfig>().is_err());
+    assert!("text = \"#10203040\"".parse::<ThemeConfig>().is_ok());
+}
+
+#[test]
+fn test_missing_font_family() {
+    let app = MenuAppBuilder::new()
+        .font_family("No Such Font Family")
+        .build::<String>();
+    assert!(matches!(app, Err(BuildError::FontNotFound(_))));
+}
+
+/// The pixels of a row in the list of a default sized menu
+fn row_pixels(image: &image::RgbaImage, row: u32) -> Vec<[u8; 4]> {
+    let top = 2 + 18 + row * 18;
+    (top..top + 18)
+        .flat_map(|y| (0..image.width()).map(move |x| image.get_pixel(x, y).0))
+        .collect()
+}
+
+/// Number of pixels in a row that are brighter than the background
+fn lit_pixels(image: &image::RgbaImage, row: u32) -> usize {
+    row_pixels(image, row)
+        .iter()
+        .filter(|pixel| pixel[..3].iter().any(|&c| c > 0x80))
+        .count()
+}
+
+#[test]
+fn test_render_markup() {
+    let items = [
+        "first",
+        "Firefox",
+        "<b>Firefox</b>",
+        "<i>Firefox</i>",
+        r#"<span foreground="red">Firefox</span>"#,
+    ];
+    let mut app: MenuApp<MarkupItem> = MenuAppBuilder::new().build().unwrap();
+    app.set_items(items.iter().map(|item| MarkupItem::new(item)).collect());
+    let image = app.render_to_image();
+
+    // bold text is drawn with thicker strokes
+    assert!(lit_pixels(&image, 2) > lit_pixels(&image, 1));
+    // italic text is slanted
+    assert_ne!(row_pixels(&image, 3), row_pixels(&image, 1));
+    // the span color reaches the text
+    let red = row_pixels(&image, 4)
+        .into_iter()
+        .any(|[r, g, b, _]| r > 0xc0 && g < 0x40 && b < 0x40);
+    assert!(red);
+}
+
+struct IconItem(Icon);
+
+impl MenuItem for IconItem {
+    fn title(&self) -> Cow<'_, str> {
+        Cow::Borrowed("icon")
+    }
+
+    fn icon(&self) -> Option<&Icon> {
+        Some(&self.0)
+    }
+}
+
+#[test]
+fn test_render_wide_icon() {
+    let icon = image::RgbaImage::from_pixel(64, 16, image::Rgba([0, 0xff, 0, 0xff]));
+    let mut app: MenuApp<IconItem> = MenuAppBuilder::new().build().unwrap();
+    app.set_items(vec![IconItem(Icon::Image(Arc::new(icon)))]);
+    let image = app.render_to_image();
+
+    // the icon is loaded before rendering and keeps its aspect ratio
+    let green: Vec<(u32, u32)> = image
+        .enumerate_pixels()
+        .filter(|(_, _, pixel)| {
+            let [r, g, b, _] = pixel.0;
+            g > 0x80 && r < 0x40 && b < 0x40
+        })
+        .map(|(x, y, _)| (x, y))
+        .collect();
+    assert!(!green.is_empty());
+    let span = |values: Vec<u32>| values.iter().max().unwrap() - values.iter().min().unwrap() + 1;
+    let width = span(green.iter().map(|&(x, _)| x).collect());
+    let height = span(green.iter().map(|&(_, y)| y).collect());
+    assert!(width >= 2 * height);
+}